@@ -0,0 +1,221 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+
+// The first Minecraft versions to require a newer Java release, newest first, as the minor
+// and patch of 1.minor.patch
+const REQUIREMENTS: [((u32, u32), u32); 2] = [((20, 5), 21), ((17, 0), 17)];
+
+// Where JDKs end up when they're installed by a package manager or by hand
+const INSTALL_DIRS: [&str; 6] = [
+    "/usr/lib/jvm",
+    "/usr/java",
+    "/opt/java",
+    "/Library/Java/JavaVirtualMachines",
+    "C:\\Program Files\\Java",
+    "C:\\Program Files\\Eclipse Adoptium",
+];
+
+/// A Java runtime found on this machine
+struct Installation {
+    path: PathBuf,
+    major: u32,
+}
+
+/// Locate a Java runtime, preferring `JAVA_HOME` over whatever is on the `PATH`
+pub fn find() -> Result<PathBuf> {
+    if let Some(home) = env::var_os("JAVA_HOME") {
+        let java = PathBuf::from(home).join("bin").join(executable());
+        if java.is_file() {
+            return Ok(java);
+        }
+    }
+
+    env::var_os("PATH")
+        .and_then(|paths| {
+            env::split_paths(&paths)
+                .map(|p| p.join(executable()))
+                .find(|p| p.is_file())
+        })
+        .ok_or_else(|| anyhow!("could not find a Java runtime, install one or set JAVA_HOME"))
+}
+
+/// Locate a Java runtime of the required major version, falling back to `find` if it is unknown
+pub fn find_for(required: Option<u32>) -> Result<PathBuf> {
+    let Some(required) = required else {
+        return find();
+    };
+
+    let installations = installations();
+
+    // The runtime `find` would have picked comes first, so it wins any ties
+    if let Some(exact) = installations.iter().find(|i| i.major == required) {
+        info!("using Java {} at {}", exact.major, exact.path.display());
+        return Ok(exact.path.clone());
+    }
+
+    if let Some(newer) = installations.iter().find(|i| i.major > required) {
+        warn!(
+            "no Java {required} runtime found, trying Java {} at {}",
+            newer.major,
+            newer.path.display()
+        );
+        return Ok(newer.path.clone());
+    }
+
+    let found: Vec<u32> = installations.iter().map(|i| i.major).collect();
+
+    Err(anyhow!(
+        "Java {required} is required, but only found Java {found:?}, install it or set JAVA_HOME"
+    ))
+}
+
+/// Warn ahead of time if no suitable Java runtime is installed
+pub fn check(required: Option<u32>) {
+    if let Err(e) = find_for(required) {
+        warn!("{e}");
+    }
+}
+
+/// The Java major version a Minecraft version needs, if it can be told
+pub fn required_version(minecraft_version: &str) -> Option<u32> {
+    // Pre-releases and release candidates like 1.20.5-pre1 need what their release does,
+    // while snapshots like 24w14a and unresolved versions like latest can't be told
+    let release = minecraft_version.split('-').next()?;
+
+    let mut parts = release.split('.').map(str::parse::<u32>);
+
+    if parts.next()?.ok()? != 1 {
+        return None;
+    }
+
+    let minor = parts.next()?.ok()?;
+    let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some(
+        REQUIREMENTS
+            .iter()
+            .find(|(first, _)| (minor, patch) >= *first)
+            .map_or(8, |(_, java)| *java),
+    )
+}
+
+/// Every Java runtime which can be found, starting with the one `find` picks
+fn installations() -> Vec<Installation> {
+    let mut candidates: Vec<PathBuf> = find().into_iter().collect();
+
+    if let Some(paths) = env::var_os("PATH") {
+        candidates.extend(env::split_paths(&paths).map(|p| p.join(executable())));
+    }
+
+    let mut dirs: Vec<PathBuf> = INSTALL_DIRS.iter().map(PathBuf::from).collect();
+
+    if let Some(home) = env::var_os("HOME") {
+        dirs.push(Path::new(&home).join(".sdkman/candidates/java"));
+        dirs.push(Path::new(&home).join(".jdks"));
+    }
+
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let home = entry.path();
+
+            // macOS bundles keep the actual JDK a few levels down
+            candidates.push(home.join("bin").join(executable()));
+            candidates.push(home.join("Contents/Home/bin").join(executable()));
+        }
+    }
+
+    let mut seen = vec![];
+    let mut installations = vec![];
+
+    for candidate in candidates {
+        let Ok(path) = candidate.canonicalize() else {
+            continue;
+        };
+
+        if seen.contains(&path) {
+            continue;
+        }
+
+        seen.push(path);
+
+        if let Some(major) = major_version(&candidate) {
+            installations.push(Installation {
+                path: candidate,
+                major,
+            });
+        }
+    }
+
+    installations
+}
+
+// `java -version` prints something like `openjdk version "21.0.2"`, or "1.8.0_392" for Java 8
+fn major_version(java: &Path) -> Option<u32> {
+    let output = Command::new(java).arg("-version").output().ok()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let version = stderr.split('"').nth(1)?;
+    let mut parts = version.split(['.', '-', '+', '_']);
+
+    match parts.next()? {
+        "1" => parts.next()?.parse().ok(),
+        major => major.parse().ok(),
+    }
+}
+
+const fn executable() -> &'static str {
+    if cfg!(windows) {
+        "java.exe"
+    } else {
+        "java"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::required_version;
+
+    #[test]
+    fn maps_releases_to_java() {
+        assert_eq!(required_version("1.8.9"), Some(8));
+        assert_eq!(required_version("1.16.5"), Some(8));
+        assert_eq!(required_version("1.17"), Some(17));
+        assert_eq!(required_version("1.20.4"), Some(17));
+        assert_eq!(required_version("1.20.5"), Some(21));
+        assert_eq!(required_version("1.21"), Some(21));
+    }
+
+    #[test]
+    fn maps_pre_releases_like_their_release() {
+        assert_eq!(required_version("1.20.5-pre1"), Some(21));
+        assert_eq!(required_version("1.17-rc1"), Some(17));
+    }
+
+    #[test]
+    fn ignores_anything_else() {
+        for version in [
+            "24w14a",
+            "latest",
+            "snapshot",
+            "3.3.0-SNAPSHOT",
+            "1.20.5.1",
+            "",
+        ] {
+            assert_eq!(required_version(version), None, "{version}");
+        }
+    }
+}
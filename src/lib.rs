@@ -1,11 +1,67 @@
-use std::{fs::File, io::Write, path::Path};
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+};
 
 use anyhow::{anyhow, Result};
 use log::info;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
 
 pub const FAKE_USER_AGENT: &str =
     "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/121.0.0.0 Safari/537.3";
 
+/// Download a file, verifying it against a checksum in the form `method#hash` if one is given
+pub fn download(url: &str, path: &Path, checksum: Option<&str>) -> Result<()> {
+    let Some(checksum) = checksum else {
+        info!("downloading jarfile from {url}");
+
+        let resp = ureq::get(url).set("User-Agent", FAKE_USER_AGENT).call()?;
+
+        if let Some(prefix) = path.parent() {
+            fs::create_dir_all(prefix)?;
+        }
+
+        let mut file = File::create(path)?;
+        io::copy(&mut resp.into_reader(), &mut file)?;
+
+        return Ok(());
+    };
+
+    let (method, hash) = split_checksum(checksum)?;
+
+    match method {
+        "sha512" => download_with_checksum::<Sha512>(url, path, hash),
+        "sha256" => download_with_checksum::<Sha256>(url, path, hash),
+        "sha1" => download_with_checksum::<Sha1>(url, path, hash),
+        "md5" => download_with_checksum::<Md5>(url, path, hash),
+        _ => Err(anyhow!("unsupported checksum method {method}")),
+    }
+}
+
+/// Whether a file on disk matches a checksum in the form `method#hash`
+pub fn matches_checksum(path: &Path, checksum: &str) -> Result<bool> {
+    let (method, hash) = split_checksum(checksum)?;
+
+    let actual = match method {
+        "sha512" => hash_file::<Sha512>(path)?,
+        "sha256" => hash_file::<Sha256>(path)?,
+        "sha1" => hash_file::<Sha1>(path)?,
+        "md5" => hash_file::<Md5>(path)?,
+        _ => return Err(anyhow!("unsupported checksum method {method}")),
+    };
+
+    Ok(actual == hash)
+}
+
+fn split_checksum(checksum: &str) -> Result<(&str, &str)> {
+    checksum
+        .split_once('#')
+        .ok_or_else(|| anyhow!("checksum {checksum} is missing a method"))
+}
+
 pub fn download_with_checksum<T: sha2::Digest + Write>(
     url: &str,
     path: &Path,
@@ -41,10 +97,7 @@ pub fn download_with_checksum<T: sha2::Digest + Write>(
         hasher.finalize()
     };
 
-    let hash = digest
-        .as_slice()
-        .iter()
-        .fold(String::new(), |acc, b| acc + &format!("{b:02x}"));
+    let hash = to_hex(digest.as_slice());
 
     if hash != wanted_hash {
         return Err(anyhow!("hashes do not match"));
@@ -52,3 +105,19 @@ pub fn download_with_checksum<T: sha2::Digest + Write>(
 
     Ok(())
 }
+
+pub fn hash_file<T: sha2::Digest + Write>(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+
+    let mut hasher = T::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(to_hex(hasher.finalize().as_slice()))
+}
+
+/// Lowercase hexadecimal, as checksums are published
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::new(), |acc, b| acc + &format!("{b:02x}"))
+}
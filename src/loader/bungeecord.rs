@@ -0,0 +1,83 @@
+use anyhow::{anyhow, Result};
+
+use super::jenkins;
+
+const BASE_URL: &str = "https://ci.md-5.net";
+const JOB: &str = "BungeeCord";
+
+pub struct BungeeCord;
+
+impl super::Loader for BungeeCord {
+    fn name(&self) -> &'static str {
+        "bungeecord"
+    }
+
+    fn location(&self) -> Option<&'static str> {
+        Some("plugins")
+    }
+
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &["bungeecord"]
+    }
+
+    fn hangar_platform(&self) -> Option<&'static str> {
+        Some("WATERFALL")
+    }
+
+    fn is_proxy(&self) -> bool {
+        true
+    }
+
+    fn is_loader_jar(&self, _minecraft_version: &str, filename: &str) -> bool {
+        filename.starts_with("bungeecord-") && filename.to_lowercase().ends_with(".jar")
+    }
+
+    fn find_build(&self, minecraft_version: &str, version: &str, _: bool) -> Result<super::Build> {
+        resolve(minecraft_version, version)
+    }
+
+    fn list(&self, _minecraft_version: Option<&str>) -> Result<()> {
+        list()
+    }
+}
+
+// BungeeCord builds support every Minecraft version at once, so only the build number matters
+pub fn resolve(minecraft_version: &str, build: &str) -> Result<super::Build> {
+    let builds = jenkins::builds(BASE_URL, JOB)?;
+
+    let (build, artifact) = builds
+        .iter()
+        .filter(|b| build == "latest" || b.number.to_string() == build)
+        .find_map(|b| find_artifact(b).map(|a| (b, a)))
+        .ok_or_else(|| anyhow!("could not find bungeecord build {build}"))?;
+
+    println!("bungeecord build {}", build.number);
+
+    Ok(super::Build {
+        minecraft_version: minecraft_version.to_string(),
+        version: build.number.to_string(),
+        url: jenkins::artifact_url(BASE_URL, JOB, build, artifact),
+        file: format!("bungeecord-{}.jar", build.number),
+        checksum: build.checksum(artifact),
+        entrypoint: None,
+    })
+}
+
+pub fn list() -> Result<()> {
+    let builds: Vec<usize> = jenkins::builds(BASE_URL, JOB)?
+        .iter()
+        .filter(|b| find_artifact(b).is_some())
+        .map(|b| b.number)
+        .collect();
+
+    super::print_listing(&builds, None);
+
+    Ok(())
+}
+
+fn find_artifact(build: &jenkins::Build) -> Option<&jenkins::Artifact> {
+    build
+        .artifacts
+        .iter()
+        .find(|a| a.file_name == "BungeeCord.jar")
+}
@@ -1,5 +1,3 @@
-use std::{fs::File, io};
-
 use anyhow::{anyhow, Result};
 use log::info;
 use serde::Deserialize;
@@ -9,9 +7,38 @@ const BASE_URL: &str = "https://meta.fabricmc.net/v2/versions";
 #[derive(Clone, Deserialize)]
 struct Version {
     version: String,
+    stable: bool,
 }
 
-pub fn fetch(minecraft_version: &str, loader_version: &str) -> Result<()> {
+pub struct Fabric;
+
+impl super::Loader for Fabric {
+    fn name(&self) -> &'static str {
+        "fabric"
+    }
+
+    fn location(&self) -> Option<&'static str> {
+        Some("mods")
+    }
+
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &["fabric"]
+    }
+
+    fn is_loader_jar(&self, _minecraft_version: &str, filename: &str) -> bool {
+        filename == "fabric.jar"
+    }
+
+    fn find_build(&self, minecraft_version: &str, version: &str, _: bool) -> Result<super::Build> {
+        resolve(minecraft_version, version)
+    }
+
+    fn list(&self, minecraft_version: Option<&str>) -> Result<()> {
+        list(minecraft_version)
+    }
+}
+
+pub fn resolve(minecraft_version: &str, loader_version: &str) -> Result<super::Build> {
     let game = get_version("/game", minecraft_version)?.version;
     let loader = get_version("/loader", loader_version)?.version;
 
@@ -29,29 +56,48 @@ pub fn fetch(minecraft_version: &str, loader_version: &str) -> Result<()> {
         .ok_or_else(|| anyhow!("failed to retrieve latest installer"))?
         .version;
 
-    let formatted_url = format!("{BASE_URL}/loader/{game}/{loader}/{installer}/server/jar");
+    let url = format!("{BASE_URL}/loader/{game}/{loader}/{installer}/server/jar");
+
+    Ok(super::Build {
+        minecraft_version: game,
+        version: loader,
+        url,
+        file: String::from("fabric.jar"),
+        checksum: None,
+        entrypoint: None,
+    })
+}
 
-    info!("downloading jarfile");
+pub fn list(minecraft_version: Option<&str>) -> Result<()> {
+    let versions = if let Some(minecraft_version) = minecraft_version {
+        // Loader builds are shared between every supported Minecraft version
+        get_version("/game", minecraft_version)?;
+        get_versions("/loader")?
+    } else {
+        get_versions("/game")?
+    };
 
-    let resp = ureq::get(&formatted_url)
-        .set("User-Agent", mup::FAKE_USER_AGENT)
-        .call()?;
+    let recommended = versions.iter().position(|v| v.stable);
+    let versions: Vec<String> = versions.into_iter().map(|v| v.version).collect();
 
-    let mut file = File::create("fabric.jar")?;
-    io::copy(&mut resp.into_reader(), &mut file)?;
+    super::print_listing(&versions, recommended);
 
     Ok(())
 }
 
+fn get_versions(path: &str) -> Result<Vec<Version>> {
+    Ok(ureq::get(&format!("{BASE_URL}{path}"))
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .call()?
+        .into_json()?)
+}
+
 fn get_version(path: &str, version: &str) -> Result<Version> {
     let stripped = path.strip_prefix('/').unwrap();
 
     info!("fetching information for {stripped} version {version}");
 
-    let versions: Vec<Version> = ureq::get(&format!("{BASE_URL}{path}"))
-        .set("User-Agent", mup::FAKE_USER_AGENT)
-        .call()?
-        .into_json()?;
+    let versions = get_versions(path)?;
 
     if version == "latest" {
         return versions
@@ -1,7 +1,7 @@
-use std::{collections::HashMap, fs::File, io, sync::LazyLock};
+use std::{collections::HashMap, path::Path, sync::LazyLock};
 
 use anyhow::{anyhow, Result};
-use log::{info, warn};
+use log::info;
 use serde::Deserialize;
 use versions::Versioning;
 
@@ -9,9 +9,6 @@ const PROMOS_URL: &str =
     "https://files.minecraftforge.net/maven/net/minecraftforge/forge/promotions_slim.json";
 const BASE_MAVEN_URL: &str = "https://maven.minecraftforge.net/net/minecraftforge/forge";
 
-// Forge does not provide installer jarfiles before Minecraft version 1.5.2
-static MINECRAFT_CUTOFF: LazyLock<Versioning> = LazyLock::new(|| Versioning::new("1.5.2").unwrap());
-
 // The cutoff in 1.9 builds after which versions are formatted as 1.X-{installer}-1.X.0
 static INSTALLER_CUTOFF_TRIPLE: LazyLock<Versioning> =
     LazyLock::new(|| Versioning::new("12.16.1.1938").unwrap());
@@ -20,19 +17,71 @@ static INSTALLER_CUTOFF_TRIPLE: LazyLock<Versioning> =
 static INSTALLER_CUTOFF_DOUBLE: LazyLock<Versioning> =
     LazyLock::new(|| Versioning::new("12.16.0.1885").unwrap());
 
+// The first Minecraft version whose installer writes run.sh and an argument file
+static RUN_SCRIPT_CUTOFF: LazyLock<Versioning> =
+    LazyLock::new(|| Versioning::new("1.17.0").unwrap());
+
 #[derive(Deserialize)]
 struct PromosResponse {
     promos: HashMap<String, String>,
 }
 
-pub fn fetch(minecraft_version: &str, installer_version: &str) -> Result<()> {
-    info!("fetching promos");
+pub struct Forge;
 
-    let promos = ureq::get(PROMOS_URL)
-        .set("User-Agent", mup::FAKE_USER_AGENT)
-        .call()?
-        .into_json::<PromosResponse>()?
-        .promos;
+impl super::Loader for Forge {
+    fn name(&self) -> &'static str {
+        "forge"
+    }
+
+    fn location(&self) -> Option<&'static str> {
+        Some("mods")
+    }
+
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &["forge"]
+    }
+
+    // Forge does not provide installer jarfiles before Minecraft version 1.5.2
+    fn oldest_minecraft_version(&self) -> Option<&'static str> {
+        Some("1.5.2")
+    }
+
+    fn is_installer(&self) -> bool {
+        true
+    }
+
+    // Installers for Minecraft 1.17 and later launch the server from an argument file,
+    // older ones produce a jarfile, which most builds before 1.12.2 call universal
+    fn installed_entrypoint(&self, build: &super::Build) -> Result<String> {
+        let minecraft = Versioning::new(&build.minecraft_version)
+            .ok_or_else(|| anyhow!("invalid minecraft version {}", build.minecraft_version))?;
+        let version_tag = get_version_tag(&minecraft, &build.version)?;
+
+        if minecraft >= *RUN_SCRIPT_CUTOFF {
+            return Ok(format!(
+                "@libraries/net/minecraftforge/forge/{version_tag}/unix_args.txt"
+            ));
+        }
+
+        let universal = format!("forge-{version_tag}-universal.jar");
+        if Path::new(&universal).exists() {
+            return Ok(universal);
+        }
+
+        Ok(format!("forge-{version_tag}.jar"))
+    }
+
+    fn find_build(&self, minecraft_version: &str, version: &str, _: bool) -> Result<super::Build> {
+        resolve(minecraft_version, version)
+    }
+
+    fn list(&self, minecraft_version: Option<&str>) -> Result<()> {
+        list(minecraft_version)
+    }
+}
+
+pub fn resolve(minecraft_version: &str, installer_version: &str) -> Result<super::Build> {
+    let promos = get_promos()?;
 
     let minecraft = if minecraft_version == "latest" {
         promos
@@ -55,31 +104,67 @@ pub fn fetch(minecraft_version: &str, installer_version: &str) -> Result<()> {
 
     let version_tag = get_version_tag(&minecraft, installer)?;
 
-    let formatted_url = format!("{BASE_MAVEN_URL}/{version_tag}/forge-{version_tag}-installer.jar");
+    let url = format!("{BASE_MAVEN_URL}/{version_tag}/forge-{version_tag}-installer.jar");
+    let checksum = super::maven_checksum(&url, "sha1")?;
+
+    Ok(super::Build {
+        minecraft_version: minecraft.to_string(),
+        version: installer.to_string(),
+        url,
+        file: format!("forge-{minecraft}-{installer}-installer.jar"),
+        checksum: Some(checksum),
+        entrypoint: None,
+    })
+}
 
-    info!("downloading installer jarfile");
+pub fn list(minecraft_version: Option<&str>) -> Result<()> {
+    let promos = get_promos()?;
 
-    let resp = ureq::get(&formatted_url)
-        .set("User-Agent", mup::FAKE_USER_AGENT)
-        .call()?;
+    let Some(minecraft_version) = minecraft_version else {
+        let mut versions: Vec<Versioning> = promos
+            .keys()
+            .filter_map(|p| p.split('-').next())
+            .filter_map(Versioning::new)
+            .collect();
+
+        versions.sort();
+        versions.dedup();
+
+        versions.reverse();
+
+        super::print_listing(&versions, None);
+
+        return Ok(());
+    };
 
-    let filename = format!("forge-{minecraft}-{installer}.jar");
+    // Forge only promotes a latest and recommended build for each Minecraft version
+    let mut found = false;
 
-    let mut file = File::create(filename)?;
-    io::copy(&mut resp.into_reader(), &mut file)?;
+    for promo in ["latest", "recommended"] {
+        if let Some(build) = promos.get(&format!("{minecraft_version}-{promo}")) {
+            println!("{build} ({promo})");
+            found = true;
+        }
+    }
 
-    warn!("this is an installer, not a server loader! please run it and install the server before proceeding.");
+    if !found {
+        return Err(anyhow!("invalid or unsupported minecraft version"));
+    }
 
     Ok(())
 }
 
-fn get_version_tag(minecraft: &Versioning, installer: &str) -> Result<String> {
-    if minecraft < &MINECRAFT_CUTOFF {
-        return Err(anyhow!(
-            "forge does not provide installer jarfiles before Minecraft 1.5.2"
-        ));
-    }
+fn get_promos() -> Result<HashMap<String, String>> {
+    info!("fetching promos");
 
+    Ok(ureq::get(PROMOS_URL)
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .call()?
+        .into_json::<PromosResponse>()?
+        .promos)
+}
+
+fn get_version_tag(minecraft: &Versioning, installer: &str) -> Result<String> {
     // Lots of edge cases here
     match minecraft {
         Versioning::Ideal(s) => {
@@ -0,0 +1,49 @@
+use std::{fs, path::Path, process::Command};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use crate::java;
+
+/// Run a loader's installer headlessly, returning what to launch the installed server with
+pub fn run<L: super::Loader + ?Sized>(loader: &L, build: &super::Build) -> Result<String> {
+    let installer = build.file.as_str();
+    // Old Forge installers in particular only run on the Java their Minecraft version shipped with
+    let java = java::find_for(java::required_version(&build.minecraft_version))?;
+
+    info!("running {installer} with {}", java.display());
+
+    let output = Command::new(java)
+        .args(["-jar", installer])
+        .args(loader.installer_args(build))
+        .output()?;
+
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<&str> = stdout.lines().collect();
+
+        return Err(anyhow!(
+            "installer exited with {}:\n{}",
+            output.status,
+            lines[lines.len().saturating_sub(10)..].join("\n")
+        ));
+    }
+
+    // Only trust what this build should have produced, a previous install can leave
+    // another loader's or build's files behind
+    let entrypoint = loader.installed_entrypoint(build)?;
+    if !Path::new(entrypoint.trim_start_matches('@')).exists() {
+        return Err(anyhow!("installer did not produce {entrypoint}"));
+    }
+
+    info!("cleaning up {installer}");
+
+    fs::remove_file(installer)?;
+
+    let log = format!("{installer}.log");
+    if Path::new(&log).exists() {
+        fs::remove_file(log)?;
+    }
+
+    Ok(entrypoint)
+}
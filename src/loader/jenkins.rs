@@ -0,0 +1,91 @@
+use anyhow::Result;
+use log::info;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Jobs {
+    jobs: Vec<Job>,
+}
+
+#[derive(Deserialize)]
+struct Job {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Builds {
+    builds: Vec<Build>,
+}
+
+#[derive(Deserialize)]
+pub struct Build {
+    pub number: usize,
+    result: Option<String>,
+    pub artifacts: Vec<Artifact>,
+    #[serde(default)]
+    fingerprint: Vec<Fingerprint>,
+}
+
+#[derive(Deserialize)]
+pub struct Artifact {
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    #[serde(rename = "relativePath")]
+    pub relative_path: String,
+}
+
+#[derive(Deserialize)]
+struct Fingerprint {
+    #[serde(rename = "fileName")]
+    file_name: String,
+    hash: String,
+}
+
+impl Build {
+    /// Jenkins fingerprints artifacts with their md5 hash, when fingerprinting is enabled
+    pub fn checksum(&self, artifact: &Artifact) -> Option<String> {
+        self.fingerprint
+            .iter()
+            .find(|f| f.file_name == artifact.file_name)
+            .map(|f| format!("md5#{}", f.hash))
+    }
+}
+
+pub fn jobs(base_url: &str) -> Result<Vec<String>> {
+    info!("fetching jobs from {base_url}");
+
+    let body: Jobs = ureq::get(&format!("{base_url}/api/json"))
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .query("tree", "jobs[name]")
+        .call()?
+        .into_json()?;
+
+    Ok(body.jobs.into_iter().map(|j| j.name).collect())
+}
+
+/// Successful builds of a job, newest first
+pub fn builds(base_url: &str, job: &str) -> Result<Vec<Build>> {
+    info!("fetching builds of {job}");
+
+    let body: Builds = ureq::get(&format!("{base_url}/job/{job}/api/json"))
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .query(
+            "tree",
+            "builds[number,result,artifacts[fileName,relativePath],fingerprint[fileName,hash]]",
+        )
+        .call()?
+        .into_json()?;
+
+    Ok(body
+        .builds
+        .into_iter()
+        .filter(|b| b.result.as_deref() == Some("SUCCESS"))
+        .collect())
+}
+
+pub fn artifact_url(base_url: &str, job: &str, build: &Build, artifact: &Artifact) -> String {
+    format!(
+        "{base_url}/job/{job}/{}/artifact/{}",
+        build.number, artifact.relative_path
+    )
+}
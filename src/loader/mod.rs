@@ -1,34 +1,336 @@
+use std::{fmt::Display, path::PathBuf};
+
 use anyhow::{anyhow, Result};
+use clap::{Args, Subcommand};
+use log::info;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use versions::Versioning;
+
+use crate::server::lockfile::Lockfile;
 
+mod bungeecord;
 mod fabric;
 mod forge;
+mod installer;
+mod jenkins;
 mod neoforge;
 mod paper;
+mod pufferfish;
+mod purpur;
+mod quilt;
+mod vanilla;
+
+// Every supported loader, in the order they are suggested on the command line
+static LOADERS: [&dyn Loader; 12] = [
+    &fabric::Fabric,
+    &forge::Forge,
+    &paper::PAPER,
+    &neoforge::NeoForge,
+    &purpur::Purpur,
+    &paper::FOLIA,
+    &pufferfish::Pufferfish,
+    &paper::VELOCITY,
+    &paper::WATERFALL,
+    &bungeecord::BungeeCord,
+    &quilt::Quilt,
+    &vanilla::Vanilla,
+];
+
+/// What mup knows about a loader, and how to get a build of it
+pub trait Loader: Sync {
+    /// Name used on the command line and in the lockfile
+    fn name(&self) -> &'static str;
+
+    /// Where mods or plugins are installed, if the loader can run any at all
+    fn location(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Which Modrinth loader tags the loader can run projects for
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Which Hangar platform the loader can run plugins for, if any
+    fn hangar_platform(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// The oldest Minecraft version builds are available for, if there is a cutoff
+    fn oldest_minecraft_version(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether the loader is a proxy, whose version says nothing about the Minecraft version
+    fn is_proxy(&self) -> bool {
+        false
+    }
+
+    /// Whether the downloaded file is an installer rather than the server itself
+    fn is_installer(&self) -> bool {
+        false
+    }
+
+    /// Arguments to run the installer headlessly with, after `-jar <installer>`
+    fn installer_args(&self, _build: &Build) -> Vec<String> {
+        vec![String::from("--installServer")]
+    }
+
+    /// What running the installer for a build leaves behind to launch the server with,
+    /// prefixed with `@` for argument files
+    fn installed_entrypoint(&self, _build: &Build) -> Result<String> {
+        Err(anyhow!("{} builds are not installers", self.name()))
+    }
+
+    /// Whether a file in the server directory was downloaded by `fetch` for this loader
+    fn is_loader_jar(&self, minecraft_version: &str, filename: &str) -> bool {
+        filename.starts_with(&format!("{}-{minecraft_version}-", self.name()))
+            && filename.to_lowercase().ends_with(".jar")
+    }
+
+    /// Resolve a requested version such as "latest" to a concrete build
+    fn find_build(
+        &self,
+        minecraft_version: &str,
+        version: &str,
+        experimental: bool,
+    ) -> Result<Build>;
+
+    /// List available Minecraft versions, or builds for a Minecraft version
+    fn list(&self, minecraft_version: Option<&str>) -> Result<()>;
+
+    /// Whether builds exist for a Minecraft version, assuming "latest" always has one
+    fn supports(&self, minecraft_version: &str) -> bool {
+        let (Some(oldest), Some(requested)) = (
+            self.oldest_minecraft_version(),
+            Versioning::new(minecraft_version),
+        ) else {
+            return true;
+        };
+
+        minecraft_version == "latest" || requested >= Versioning::new(oldest).unwrap()
+    }
+
+    /// Resolve a requested version, refusing Minecraft versions the loader has no builds for
+    fn resolve(&self, minecraft_version: &str, version: &str, experimental: bool) -> Result<Build> {
+        if !self.supports(minecraft_version) {
+            return Err(anyhow!(
+                "{} does not support Minecraft versions before {}",
+                self.name(),
+                self.oldest_minecraft_version().unwrap_or_default()
+            ));
+        }
+
+        self.find_build(minecraft_version, version, experimental)
+    }
+
+    /// Download a build, running it first if it is an installer
+    fn download(&self, build: &mut Build) -> Result<()> {
+        let path = PathBuf::from(&build.file);
+
+        mup::download(&build.url, &path, build.checksum.as_deref())?;
+
+        // Some loaders, like Fabric's server launcher, publish no checksum at all,
+        // so lock whatever was downloaded first and verify against that from then on
+        if build.checksum.is_none() {
+            build.checksum = Some(format!("sha256#{}", mup::hash_file::<Sha256>(&path)?));
+        }
+
+        build.entrypoint = if self.is_installer() {
+            Some(installer::run(self, build)?)
+        } else {
+            Some(build.file.clone())
+        };
+
+        Ok(())
+    }
+
+    /// Resolve and download a build in one go
+    fn fetch(&self, minecraft_version: &str, version: &str, experimental: bool) -> Result<Build> {
+        let mut build = self.resolve(minecraft_version, version, experimental)?;
+
+        self.download(&mut build)?;
 
-const VALID_LOADERS: [&str; 4] = ["fabric", "forge", "paper", "neoforge"];
+        Ok(build)
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct LoaderArgs {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Name of the loader to download
+    #[arg(short, long, value_name = "loader", value_parser = parse, required = true)]
+    name: Option<String>,
+
+    /// Minecraft version to target
+    #[arg(short, long, default_value = "latest")]
+    minecraft_version: String,
+
+    /// Loader version to target
+    #[arg(short, long, default_value = "latest")]
+    version: String,
+
+    /// Allow experimental builds when targeting the latest version
+    #[arg(short, long, action)]
+    experimental: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Move the server's locked loader to another build
+    Update {
+        /// Loader version to target
+        #[arg(short, long, default_value = "latest")]
+        version: String,
+
+        /// Allow experimental builds when targeting the latest version
+        #[arg(short, long, action)]
+        experimental: bool,
+    },
 
-pub fn fetch(loader: &str, minecraft_version: &str, version: &str) -> Result<()> {
-    match loader {
-        "paper" => paper::fetch(minecraft_version, version),
-        "fabric" => fabric::fetch(minecraft_version, version),
-        "forge" => forge::fetch(minecraft_version, version),
-        "neoforge" => neoforge::fetch(minecraft_version),
-        l => Err(anyhow!("{l} is currently unsupported")),
+    /// List available Minecraft versions, or loader builds for a Minecraft version
+    List {
+        /// Name of the loader to list versions of
+        #[arg(value_name = "loader", value_parser = parse)]
+        name: String,
+
+        /// List loader builds for this Minecraft version
+        #[arg(short, long)]
+        minecraft_version: Option<String>,
+    },
+}
+
+/// A concrete loader build, resolved from a requested version such as "latest"
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Build {
+    pub minecraft_version: String,
+    pub version: String,
+    pub url: String,
+    pub file: String,
+    pub checksum: Option<String>,
+    /// The jarfile or argument file to launch, known once the build is installed
+    pub entrypoint: Option<String>,
+}
+
+pub fn action(loader: &LoaderArgs) -> Result<()> {
+    match &loader.command {
+        Some(Command::Update {
+            version,
+            experimental,
+        }) => update(version, *experimental),
+        Some(Command::List {
+            name,
+            minecraft_version,
+        }) => get(name)?.list(minecraft_version.as_deref()),
+        None => {
+            let name = loader.name.as_ref().unwrap();
+            let build = get(name)?.fetch(
+                &loader.minecraft_version,
+                &loader.version,
+                loader.experimental,
+            )?;
+
+            let mut lf = Lockfile::init()?;
+            if !lf.is_initialized() || lf.loader.name != *name {
+                info!("no {name} server in the current directory, not recording build");
+                return Ok(());
+            }
+
+            // Moving Minecraft versions has to go through the plugin checks in server upgrade
+            if build.minecraft_version != lf.loader.minecraft_version {
+                return Err(anyhow!(
+                    "this server is locked to minecraft {}, not {}, run mup server upgrade to move it or mup loader update to change builds",
+                    lf.loader.minecraft_version,
+                    build.minecraft_version
+                ));
+            }
+
+            lf.loader.lock(build);
+            lf.save()
+        }
     }
 }
 
-pub fn location(loader: &str) -> &str {
-    match loader {
-        "paper" => "plugins",
-        "fabric" | "forge" | "neoforge" => "mods",
-        _ => unreachable!(),
+fn update(version: &str, experimental: bool) -> Result<()> {
+    let mut lf = Lockfile::init()?;
+    if !lf.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    let kind = lf.loader.kind()?;
+
+    let mut build = kind.resolve(&lf.loader.minecraft_version, version, experimental)?;
+
+    if build.version == lf.loader.version && lf.loader.build().is_some() {
+        info!("{} is already at {}", lf.loader.name, build.version);
+        return Ok(());
     }
+
+    kind.download(&mut build)?;
+
+    println!(
+        "{} {} -> {}",
+        lf.loader.name, lf.loader.version, build.version
+    );
+
+    lf.loader.lock(build);
+    lf.save()
 }
 
-pub fn parse(input: &str) -> Result<String> {
-    if !VALID_LOADERS.contains(&input) {
-        return Err(anyhow!("try one of {VALID_LOADERS:?}"));
+/// Print versions ordered newest first, marking the latest and recommended ones
+fn print_listing<T: Display>(versions: &[T], recommended: Option<usize>) {
+    for (i, version) in versions.iter().enumerate() {
+        let mut markers = vec![];
+
+        if i == 0 {
+            markers.push("latest");
+        }
+
+        if Some(i) == recommended {
+            markers.push("recommended");
+        }
+
+        if markers.is_empty() {
+            println!("{version}");
+        } else {
+            println!("{version} ({})", markers.join(", "));
+        }
     }
+}
+
+/// Fetch the checksum published next to a Maven artifact, such as `<url>.sha1`
+fn maven_checksum(url: &str, method: &str) -> Result<String> {
+    info!("fetching {method} checksum for {url}");
+
+    let body = ureq::get(&format!("{url}.{method}"))
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .call()?
+        .into_string()?;
 
-    Ok(input.to_string())
+    let hash = body
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| anyhow!("{url}.{method} is empty"))?;
+
+    Ok(format!("{method}#{}", hash.to_lowercase()))
+}
+
+/// Look up a loader by the name used on the command line and in the lockfile
+pub fn get(name: &str) -> Result<&'static dyn Loader> {
+    LOADERS
+        .iter()
+        .find(|l| l.name() == name)
+        .copied()
+        .ok_or_else(|| {
+            let names: Vec<&str> = LOADERS.iter().map(|l| l.name()).collect();
+            anyhow!("try one of {names:?}")
+        })
+}
+
+pub fn parse(input: &str) -> Result<String> {
+    Ok(get(input)?.name().to_string())
 }
@@ -1,61 +1,199 @@
-use std::{fs::File, io, sync::LazyLock};
-
 use anyhow::{anyhow, Result};
-use log::{info, warn};
+use log::info;
 use serde::Deserialize;
 use versions::Versioning;
 
-static CUTOFF: LazyLock<Versioning> =
-    LazyLock::new(|| Versioning::new("1.20.1").unwrap());
-
-const API_URL: &str = "https://maven.neoforged.net/api/maven/latest/version/releases/net/neoforged/neoforge";
+const VERSIONS_URL: &str =
+    "https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge";
 const DOWNLOAD_URL: &str = "https://maven.neoforged.net/releases/net/neoforged/neoforge";
 
 #[derive(Deserialize)]
-struct Installer {
-    version: String,
+struct Versions {
+    versions: Vec<String>,
+}
+
+pub struct NeoForge;
+
+impl super::Loader for NeoForge {
+    fn name(&self) -> &'static str {
+        "neoforge"
+    }
+
+    fn location(&self) -> Option<&'static str> {
+        Some("mods")
+    }
+
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &["neoforge"]
+    }
+
+    // NeoForge builds for 1.20.1 exist, but are not recommended over Forge
+    fn oldest_minecraft_version(&self) -> Option<&'static str> {
+        Some("1.20.2")
+    }
+
+    fn is_installer(&self) -> bool {
+        true
+    }
+
+    fn installed_entrypoint(&self, build: &super::Build) -> Result<String> {
+        Ok(format!(
+            "@libraries/net/neoforged/neoforge/{}/unix_args.txt",
+            build.version
+        ))
+    }
+
+    fn find_build(&self, minecraft_version: &str, version: &str, _: bool) -> Result<super::Build> {
+        resolve(minecraft_version, version)
+    }
+
+    fn list(&self, minecraft_version: Option<&str>) -> Result<()> {
+        list(minecraft_version)
+    }
 }
 
 // see https://github.com/neoforged/websites/blob/main/assets/js/neoforge.js
-pub fn fetch(minecraft_version: &str) -> Result<()> {
-    if minecraft_version == "latest" {
-        return Err(anyhow!(
-            "for neoforge, you must specify a minecraft version to target"
-        ));
+pub fn resolve(minecraft_version: &str, version: &str) -> Result<super::Build> {
+    let mut versions = get_versions()?;
+    versions.sort_by_cached_key(|v| Versioning::new(v));
+
+    let minecraft = if minecraft_version == "latest" {
+        versions
+            .last()
+            .and_then(|v| minecraft_version_of(v))
+            .ok_or_else(|| anyhow!("could not get latest minecraft version"))?
+    } else {
+        minecraft_version.to_string()
+    };
+
+    let mut candidates = versions
+        .into_iter()
+        .filter(|v| minecraft_version_of(v).as_ref() == Some(&minecraft));
+
+    let installer = if version == "latest" {
+        let candidates: Vec<String> = candidates.collect();
+
+        latest_build(&candidates)
+            .cloned()
+            .ok_or_else(|| anyhow!("no neoforge builds exist for Minecraft {minecraft}"))?
+    } else {
+        candidates.find(|v| v == version).ok_or_else(|| {
+            anyhow!("neoforge version {version} does not exist for Minecraft {minecraft}")
+        })?
+    };
+
+    let url = format!("{DOWNLOAD_URL}/{installer}/neoforge-{installer}-installer.jar");
+    let checksum = super::maven_checksum(&url, "sha256")?;
+
+    Ok(super::Build {
+        file: format!("neoforge-{minecraft}-{installer}-installer.jar"),
+        minecraft_version: minecraft,
+        version: installer,
+        url,
+        checksum: Some(checksum),
+        entrypoint: None,
+    })
+}
+
+pub fn list(minecraft_version: Option<&str>) -> Result<()> {
+    let mut versions = get_versions()?;
+    versions.sort_by_cached_key(|v| Versioning::new(v));
+
+    let mut listed: Vec<&String> = vec![];
+    let mut minecraft_versions: Vec<String> = vec![];
+
+    for version in versions.iter().rev() {
+        let Some(minecraft) = minecraft_version_of(version) else {
+            continue;
+        };
+
+        match minecraft_version {
+            Some(m) if m == minecraft => listed.push(version),
+            None if !minecraft_versions.contains(&minecraft) => minecraft_versions.push(minecraft),
+            _ => (),
+        }
     }
 
-    let parsed_version = Versioning::new(minecraft_version).unwrap();
+    if minecraft_version.is_none() {
+        super::print_listing(&minecraft_versions, None);
 
-    if parsed_version <= *CUTOFF {
-        return Err(anyhow!(
-            "neoforge is not recommended for Minecraft versions before 1.20.2"
-        ));
+        return Ok(());
     }
 
-    info!("fetching latest installer version for minecraft {minecraft_version}");
+    if listed.is_empty() {
+        return Err(anyhow!("invalid or unsupported minecraft version"));
+    }
+
+    // Anything that isn't a beta is considered stable
+    let recommended = listed.iter().position(|v| !v.contains('-'));
+
+    super::print_listing(&listed, recommended);
 
-    let installer: Installer = ureq::get(&API_URL)
+    Ok(())
+}
+
+fn get_versions() -> Result<Vec<String>> {
+    info!("fetching installer versions");
+
+    let body: Versions = ureq::get(VERSIONS_URL)
         .set("User-Agent", mup::FAKE_USER_AGENT)
         .call()?
         .into_json()?;
 
-    let installer_url = format!(
-        "{DOWNLOAD_URL}/{}/neoforge-{}-installer.jar",
-        installer.version, installer.version
-    );
+    Ok(body.versions)
+}
 
-    info!("downloading installer jarfile");
+// Prefer the newest stable build, but early builds for a Minecraft version are all betas.
+// Builds must be sorted oldest first.
+fn latest_build(builds: &[String]) -> Option<&String> {
+    builds
+        .iter()
+        .rev()
+        .find(|v| !v.contains('-'))
+        .or_else(|| builds.last())
+}
 
-    let resp = ureq::get(&installer_url)
-        .set("User-Agent", mup::FAKE_USER_AGENT)
-        .call()?;
+// NeoForge versions are formatted as <minor>.<patch>.<build>, where the
+// Minecraft version they target is 1.<minor>.<patch>, or 1.<minor> for patch 0
+fn minecraft_version_of(version: &str) -> Option<String> {
+    let mut parts = version.split('.');
 
-    let filename = format!("neoforge-{minecraft_version}-{}.jar", installer.version);
+    let minor = parts.next()?;
+    let patch = parts.next()?;
 
-    let mut file = File::create(filename)?;
-    io::copy(&mut resp.into_reader(), &mut file)?;
+    if patch == "0" {
+        return Some(format!("1.{minor}"));
+    }
 
-    warn!("this is an installer, not a server loader! please run it and install the server before proceeding.");
+    Some(format!("1.{minor}.{patch}"))
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::{latest_build, minecraft_version_of};
+
+    #[test]
+    fn maps_builds_to_minecraft_versions() {
+        assert_eq!(
+            minecraft_version_of("20.2.3-beta").as_deref(),
+            Some("1.20.2")
+        );
+        assert_eq!(minecraft_version_of("20.4.80").as_deref(), Some("1.20.4"));
+        assert_eq!(minecraft_version_of("21.0.1").as_deref(), Some("1.21"));
+        assert_eq!(minecraft_version_of("21"), None);
+    }
+
+    #[test]
+    fn prefers_stable_builds() {
+        let builds = ["20.4.1-beta", "20.4.70", "20.4.80", "20.4.81-beta"].map(String::from);
+        assert_eq!(latest_build(&builds).map(String::as_str), Some("20.4.80"));
+
+        let betas = ["21.0.1-beta", "21.0.2-beta"].map(String::from);
+        assert_eq!(
+            latest_build(&betas).map(String::as_str),
+            Some("21.0.2-beta")
+        );
+
+        assert_eq!(latest_build(&[]), None);
+    }
 }
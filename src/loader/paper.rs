@@ -1,12 +1,9 @@
-use std::path::PathBuf;
-
 use anyhow::{anyhow, Result};
-use log::info;
-use mup::download_with_checksum;
+use log::{info, warn};
 use serde::Deserialize;
-use sha2::Sha256;
 
-const BASE_URL: &str = "https://api.papermc.io/v2/projects/paper";
+// Every PaperMC project, such as Folia, is served from the same API
+const BASE_URL: &str = "https://api.papermc.io/v2/projects";
 
 #[derive(Deserialize)]
 struct Versions {
@@ -20,10 +17,18 @@ struct Builds {
 
 #[derive(Clone, Default, Deserialize)]
 struct Build {
-    build: usize,
+    #[serde(rename = "build")]
+    id: usize,
+    channel: String,
+    changes: Vec<Change>,
     downloads: Downloads,
 }
 
+#[derive(Clone, Default, Deserialize)]
+struct Change {
+    summary: String,
+}
+
 #[derive(Clone, Default, Deserialize)]
 struct Downloads {
     application: Application,
@@ -34,69 +39,216 @@ struct Application {
     sha256: String,
 }
 
-pub fn fetch(minecraft_version: &str, build: &str) -> Result<()> {
+/// Modrinth loaders for the Paper, Spigot and Bukkit APIs, which Paper and every fork of it
+/// can run, such as Folia, Purpur and Pufferfish
+pub const PAPER_API_LOADERS: [&str; 3] = ["paper", "spigot", "bukkit"];
+
+/// `PAPER_API_LOADERS` after a Paper fork's own Modrinth loader
+pub const fn paper_fork_loaders(own: &'static str) -> [&'static str; 4] {
+    [
+        own,
+        PAPER_API_LOADERS[0],
+        PAPER_API_LOADERS[1],
+        PAPER_API_LOADERS[2],
+    ]
+}
+
+/// Loaders served from the same API as Paper, which only differ in what they can run
+pub struct Project {
+    name: &'static str,
+    modrinth_loaders: &'static [&'static str],
+    hangar_platform: &'static str,
+    is_proxy: bool,
+}
+
+pub const PAPER: Project = Project {
+    name: "paper",
+    modrinth_loaders: &PAPER_API_LOADERS,
+    hangar_platform: "PAPER",
+    is_proxy: false,
+};
+
+pub const FOLIA: Project = Project {
+    name: "folia",
+    modrinth_loaders: &paper_fork_loaders("folia"),
+    hangar_platform: "PAPER",
+    is_proxy: false,
+};
+
+pub const VELOCITY: Project = Project {
+    name: "velocity",
+    modrinth_loaders: &["velocity"],
+    hangar_platform: "VELOCITY",
+    is_proxy: true,
+};
+
+// Waterfall is a BungeeCord fork, so it runs BungeeCord plugins too
+pub const WATERFALL: Project = Project {
+    name: "waterfall",
+    modrinth_loaders: &["waterfall", "bungeecord"],
+    hangar_platform: "WATERFALL",
+    is_proxy: true,
+};
+
+impl super::Loader for Project {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn location(&self) -> Option<&'static str> {
+        Some("plugins")
+    }
+
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        self.modrinth_loaders
+    }
+
+    fn hangar_platform(&self) -> Option<&'static str> {
+        Some(self.hangar_platform)
+    }
+
+    fn is_proxy(&self) -> bool {
+        self.is_proxy
+    }
+
+    fn find_build(
+        &self,
+        minecraft_version: &str,
+        version: &str,
+        experimental: bool,
+    ) -> Result<super::Build> {
+        resolve(self.name, minecraft_version, version, experimental)
+    }
+
+    fn list(&self, minecraft_version: Option<&str>) -> Result<()> {
+        list(self.name, minecraft_version)
+    }
+}
+
+pub fn resolve(
+    project: &str,
+    minecraft_version: &str,
+    build: &str,
+    experimental: bool,
+) -> Result<super::Build> {
     let minecraft = if minecraft_version == "latest" {
-        get_latest_version()?
+        get_latest_version(project)?
     } else {
         minecraft_version.to_string()
     };
 
-    let build = get_build(&minecraft, build)?;
+    let build = get_build(project, &minecraft, build, experimental)?;
 
-    let formatted_url = format!(
-        "{BASE_URL}/versions/{minecraft}/builds/{}/downloads/paper-{minecraft}-{}.jar",
-        build.build, build.build,
+    println!(
+        "{project} {minecraft} build {} ({})",
+        build.id, build.channel
     );
+    for change in &build.changes {
+        println!("  - {}", change.summary);
+    }
+
+    let file = format!("{project}-{minecraft}-{}.jar", build.id);
+
+    let url = format!(
+        "{BASE_URL}/{project}/versions/{minecraft}/builds/{}/downloads/{file}",
+        build.id,
+    );
+
+    Ok(super::Build {
+        minecraft_version: minecraft,
+        version: build.id.to_string(),
+        url,
+        file,
+        checksum: Some(format!("sha256#{}", build.downloads.application.sha256)),
+        entrypoint: None,
+    })
+}
 
-    let filename = format!("paper-{minecraft}-{}.jar", build.build);
+pub fn list(project: &str, minecraft_version: Option<&str>) -> Result<()> {
+    let Some(minecraft_version) = minecraft_version else {
+        let mut versions = get_versions(project)?;
+        versions.reverse();
 
-    download_with_checksum::<Sha256>(
-        &formatted_url,
-        &PathBuf::from(filename),
-        &build.downloads.application.sha256,
-    )?;
+        super::print_listing(&versions, None);
+
+        return Ok(());
+    };
+
+    let mut builds = get_builds(project, minecraft_version)?;
+    builds.reverse();
+
+    // The newest build in the default channel is what "latest" resolves to
+    let recommended = builds.iter().position(|b| b.channel == "default");
+    let builds: Vec<usize> = builds.iter().map(|b| b.id).collect();
+
+    super::print_listing(&builds, recommended);
 
     Ok(())
 }
 
-fn get_latest_version() -> Result<String, anyhow::Error> {
-    info!("fetching latest Minecraft version");
+fn get_versions(project: &str) -> Result<Vec<String>> {
+    info!("fetching Minecraft versions");
 
-    let body: Versions = ureq::get(BASE_URL)
+    let body: Versions = ureq::get(&format!("{BASE_URL}/{project}"))
         .set("User-Agent", mup::FAKE_USER_AGENT)
         .call()?
         .into_json()?;
 
-    let latest = body
-        .versions
-        .last()
-        .ok_or_else(|| anyhow!("could not get latest minecraft version"))?
-        .to_string();
-
-    Ok(latest.replace('"', ""))
+    Ok(body.versions)
 }
 
-fn get_build(minecraft_version: &str, build: &str) -> Result<Build> {
-    let formatted_url = format!("{BASE_URL}/versions/{minecraft_version}/builds");
+fn get_builds(project: &str, minecraft_version: &str) -> Result<Vec<Build>> {
+    let formatted_url = format!("{BASE_URL}/{project}/versions/{minecraft_version}/builds");
 
-    info!("fetching build {build} for {minecraft_version}");
+    info!("fetching builds for {minecraft_version}");
 
     let body: Builds = ureq::get(formatted_url.as_str())
         .set("User-Agent", mup::FAKE_USER_AGENT)
         .call()?
         .into_json()?;
 
+    Ok(body.builds)
+}
+
+fn get_latest_version(project: &str) -> Result<String, anyhow::Error> {
+    let latest = get_versions(project)?
+        .pop()
+        .ok_or_else(|| anyhow!("could not get latest minecraft version"))?;
+
+    Ok(latest.replace('"', ""))
+}
+
+fn get_build(
+    project: &str,
+    minecraft_version: &str,
+    build: &str,
+    experimental: bool,
+) -> Result<Build> {
+    let mut builds = get_builds(project, minecraft_version)?;
+
+    // Builds are listed oldest first, and anything outside the default channel is experimental
     if build == "latest" {
-        return Ok(body.builds.first().unwrap().clone());
+        return builds
+            .into_iter()
+            .rev()
+            .find(|b| experimental || b.channel == "default")
+            .ok_or_else(|| {
+                anyhow!("no stable builds exist for {minecraft_version}, allow experimental builds to continue")
+            });
     }
 
+    info!("looking for build {build} of {minecraft_version}");
+
     let build_id: usize = build.parse()?;
 
-    let latest_build = body
-        .builds
+    let index = builds
         .iter()
-        .find(|p| p.build == build_id)
+        .position(|p| p.id == build_id)
         .ok_or_else(|| anyhow!("could not get specific loader version"))?;
 
-    Ok(latest_build.clone())
+    if builds[index].channel != "default" && !experimental {
+        warn!("build {build} of {minecraft_version} is experimental");
+    }
+
+    Ok(builds.swap_remove(index))
 }
@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Result};
+use versions::Versioning;
+
+use super::jenkins;
+
+const BASE_URL: &str = "https://ci.pufferfish.host";
+
+pub struct Pufferfish;
+
+impl super::Loader for Pufferfish {
+    fn name(&self) -> &'static str {
+        "pufferfish"
+    }
+
+    fn location(&self) -> Option<&'static str> {
+        Some("plugins")
+    }
+
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &super::paper::PAPER_API_LOADERS
+    }
+
+    fn hangar_platform(&self) -> Option<&'static str> {
+        Some("PAPER")
+    }
+
+    fn find_build(&self, minecraft_version: &str, version: &str, _: bool) -> Result<super::Build> {
+        resolve(minecraft_version, version)
+    }
+
+    fn list(&self, minecraft_version: Option<&str>) -> Result<()> {
+        list(minecraft_version)
+    }
+}
+
+pub fn resolve(minecraft_version: &str, build: &str) -> Result<super::Build> {
+    if minecraft_version == "latest" {
+        return Err(anyhow!(
+            "for pufferfish, you must specify a minecraft version to target"
+        ));
+    }
+
+    let job = job_for(minecraft_version)?;
+
+    let builds = jenkins::builds(BASE_URL, &job)?;
+
+    let (build, artifact) = builds
+        .iter()
+        .filter(|b| build == "latest" || b.number.to_string() == build)
+        .find_map(|b| find_artifact(b, minecraft_version).map(|a| (b, a)))
+        .ok_or_else(|| {
+            anyhow!("could not find pufferfish build {build} for Minecraft {minecraft_version}")
+        })?;
+
+    println!("pufferfish {minecraft_version} build {}", build.number);
+
+    Ok(super::Build {
+        minecraft_version: minecraft_version.to_string(),
+        version: build.number.to_string(),
+        url: jenkins::artifact_url(BASE_URL, &job, build, artifact),
+        file: format!("pufferfish-{minecraft_version}-{}.jar", build.number),
+        checksum: build.checksum(artifact),
+        entrypoint: None,
+    })
+}
+
+pub fn list(minecraft_version: Option<&str>) -> Result<()> {
+    let Some(minecraft_version) = minecraft_version else {
+        let mut versions: Vec<Versioning> = jenkins::jobs(BASE_URL)?
+            .iter()
+            .filter_map(|j| j.strip_prefix("Pufferfish-"))
+            .filter_map(Versioning::new)
+            .collect();
+
+        versions.sort();
+        versions.reverse();
+
+        super::print_listing(&versions, None);
+
+        return Ok(());
+    };
+
+    let builds: Vec<usize> = jenkins::builds(BASE_URL, &job_for(minecraft_version)?)?
+        .iter()
+        .filter(|b| find_artifact(b, minecraft_version).is_some())
+        .map(|b| b.number)
+        .collect();
+
+    super::print_listing(&builds, None);
+
+    Ok(())
+}
+
+// Each job builds every patch release of a Minecraft version, e.g. Pufferfish-1.20
+fn job_for(minecraft_version: &str) -> Result<String> {
+    let mut parts = minecraft_version.split('.');
+
+    match (parts.next(), parts.next()) {
+        (Some(major), Some(minor)) => Ok(format!("Pufferfish-{major}.{minor}")),
+        _ => Err(anyhow!("minecraft version {minecraft_version} is invalid")),
+    }
+}
+
+fn find_artifact<'a>(
+    build: &'a jenkins::Build,
+    minecraft_version: &str,
+) -> Option<&'a jenkins::Artifact> {
+    build.artifacts.iter().find(|a| {
+        a.file_name.contains(&format!("-{minecraft_version}-"))
+            && std::path::Path::new(&a.file_name)
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("jar"))
+    })
+}
@@ -0,0 +1,147 @@
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::Deserialize;
+
+const BASE_URL: &str = "https://api.purpurmc.org/v2/purpur";
+
+#[derive(Deserialize)]
+struct Versions {
+    versions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    builds: Builds,
+}
+
+#[derive(Deserialize)]
+struct Builds {
+    latest: String,
+    all: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Build {
+    #[serde(rename = "build")]
+    id: String,
+    result: String,
+    md5: String,
+    commits: Vec<Commit>,
+}
+
+#[derive(Deserialize)]
+struct Commit {
+    description: String,
+}
+
+pub struct Purpur;
+
+impl super::Loader for Purpur {
+    fn name(&self) -> &'static str {
+        "purpur"
+    }
+
+    fn location(&self) -> Option<&'static str> {
+        Some("plugins")
+    }
+
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        const LOADERS: [&str; 4] = super::paper::paper_fork_loaders("purpur");
+
+        &LOADERS
+    }
+
+    fn hangar_platform(&self) -> Option<&'static str> {
+        Some("PAPER")
+    }
+
+    fn find_build(&self, minecraft_version: &str, version: &str, _: bool) -> Result<super::Build> {
+        resolve(minecraft_version, version)
+    }
+
+    fn list(&self, minecraft_version: Option<&str>) -> Result<()> {
+        list(minecraft_version)
+    }
+}
+
+pub fn resolve(minecraft_version: &str, build: &str) -> Result<super::Build> {
+    let minecraft = if minecraft_version == "latest" {
+        get_versions()?
+            .pop()
+            .ok_or_else(|| anyhow!("could not get latest minecraft version"))?
+    } else {
+        minecraft_version.to_string()
+    };
+
+    let build = if build == "latest" {
+        get_version(&minecraft)?.builds.latest
+    } else {
+        build.to_string()
+    };
+
+    info!("fetching build {build} for {minecraft}");
+
+    let build: Build = ureq::get(&format!("{BASE_URL}/{minecraft}/{build}"))
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .call()?
+        .into_json()?;
+
+    if build.result != "SUCCESS" {
+        return Err(anyhow!("build {} of {minecraft} failed", build.id));
+    }
+
+    println!("purpur {minecraft} build {}", build.id);
+    for commit in &build.commits {
+        println!(
+            "  - {}",
+            commit.description.lines().next().unwrap_or_default()
+        );
+    }
+
+    Ok(super::Build {
+        url: format!("{BASE_URL}/{minecraft}/{}/download", build.id),
+        file: format!("purpur-{minecraft}-{}.jar", build.id),
+        minecraft_version: minecraft,
+        version: build.id,
+        checksum: Some(format!("md5#{}", build.md5)),
+        entrypoint: None,
+    })
+}
+
+pub fn list(minecraft_version: Option<&str>) -> Result<()> {
+    let Some(minecraft_version) = minecraft_version else {
+        let mut versions = get_versions()?;
+        versions.reverse();
+
+        super::print_listing(&versions, None);
+
+        return Ok(());
+    };
+
+    let mut builds = get_version(minecraft_version)?.builds.all;
+    builds.reverse();
+
+    super::print_listing(&builds, None);
+
+    Ok(())
+}
+
+fn get_versions() -> Result<Vec<String>> {
+    info!("fetching Minecraft versions");
+
+    let body: Versions = ureq::get(BASE_URL)
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .call()?
+        .into_json()?;
+
+    Ok(body.versions)
+}
+
+fn get_version(minecraft_version: &str) -> Result<Version> {
+    info!("fetching builds for {minecraft_version}");
+
+    Ok(ureq::get(&format!("{BASE_URL}/{minecraft_version}"))
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .call()?
+        .into_json()?)
+}
@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::Deserialize;
+
+const BASE_URL: &str = "https://meta.quiltmc.org/v3/versions";
+
+#[derive(Clone, Deserialize)]
+struct Version {
+    version: String,
+    // Only game versions are flagged, loader versions mark prereleases with a suffix instead
+    stable: Option<bool>,
+}
+
+impl Version {
+    fn is_stable(&self) -> bool {
+        self.stable.unwrap_or_else(|| !self.version.contains('-'))
+    }
+}
+
+#[derive(Deserialize)]
+struct Installer {
+    url: String,
+}
+
+pub struct Quilt;
+
+impl super::Loader for Quilt {
+    fn name(&self) -> &'static str {
+        "quilt"
+    }
+
+    fn location(&self) -> Option<&'static str> {
+        Some("mods")
+    }
+
+    // Quilt can load most Fabric mods as well
+    fn modrinth_loaders(&self) -> &'static [&'static str] {
+        &["quilt", "fabric"]
+    }
+
+    fn is_installer(&self) -> bool {
+        true
+    }
+
+    // Quilt's installer needs to be told exactly what to install
+    fn installer_args(&self, build: &super::Build) -> Vec<String> {
+        vec![
+            String::from("install"),
+            String::from("server"),
+            build.minecraft_version.clone(),
+            build.version.clone(),
+            String::from("--download-server"),
+            String::from("--install-dir=."),
+        ]
+    }
+
+    // Launches the server.jar downloaded next to it
+    fn installed_entrypoint(&self, _build: &super::Build) -> Result<String> {
+        Ok(String::from("quilt-server-launch.jar"))
+    }
+
+    fn find_build(&self, minecraft_version: &str, version: &str, _: bool) -> Result<super::Build> {
+        resolve(minecraft_version, version)
+    }
+
+    fn list(&self, minecraft_version: Option<&str>) -> Result<()> {
+        list(minecraft_version)
+    }
+}
+
+pub fn resolve(minecraft_version: &str, loader_version: &str) -> Result<super::Build> {
+    let game = get_version("/game", minecraft_version)?.version;
+    let loader = get_version("/loader", loader_version)?.version;
+
+    info!("fetching latest installer");
+
+    let installers: Vec<Installer> = ureq::get(&format!("{BASE_URL}/installer"))
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .call()?
+        .into_json()?;
+
+    let url = installers
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow!("failed to retrieve latest installer"))?
+        .url;
+
+    let checksum = super::maven_checksum(&url, "sha1")?;
+
+    Ok(super::Build {
+        file: format!("quilt-{game}-{loader}-installer.jar"),
+        minecraft_version: game,
+        version: loader,
+        url,
+        checksum: Some(checksum),
+        entrypoint: None,
+    })
+}
+
+pub fn list(minecraft_version: Option<&str>) -> Result<()> {
+    let versions = if let Some(minecraft_version) = minecraft_version {
+        // Like Fabric, loader builds are shared between every supported Minecraft version
+        get_version("/game", minecraft_version)?;
+        get_versions("/loader")?
+    } else {
+        get_versions("/game")?
+    };
+
+    let recommended = versions.iter().position(Version::is_stable);
+    let versions: Vec<String> = versions.into_iter().map(|v| v.version).collect();
+
+    super::print_listing(&versions, recommended);
+
+    Ok(())
+}
+
+fn get_versions(path: &str) -> Result<Vec<Version>> {
+    Ok(ureq::get(&format!("{BASE_URL}{path}"))
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .call()?
+        .into_json()?)
+}
+
+fn get_version(path: &str, version: &str) -> Result<Version> {
+    let stripped = path.strip_prefix('/').unwrap();
+
+    info!("fetching information for {stripped} version {version}");
+
+    let versions = get_versions(path)?;
+
+    // Prefer the newest stable version, but fall back to a prerelease if that's all there is
+    if version == "latest" {
+        return versions
+            .iter()
+            .find(|v| v.is_stable())
+            .or_else(|| versions.first())
+            .ok_or_else(|| anyhow!("failed to fetch latest {stripped} version"))
+            .cloned();
+    }
+
+    versions
+        .iter()
+        .find(|p| p.version == version)
+        .ok_or_else(|| anyhow!("{stripped} version {version} does not exist"))
+        .cloned()
+}
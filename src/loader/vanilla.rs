@@ -0,0 +1,134 @@
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::Deserialize;
+
+const MANIFEST_URL: &str = "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+
+#[derive(Deserialize)]
+struct Manifest {
+    latest: Latest,
+    versions: Vec<ManifestVersion>,
+}
+
+#[derive(Deserialize)]
+struct Latest {
+    release: String,
+    snapshot: String,
+}
+
+#[derive(Deserialize)]
+struct ManifestVersion {
+    id: String,
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct VersionInfo {
+    downloads: Downloads,
+}
+
+#[derive(Deserialize)]
+struct Downloads {
+    server: Option<Download>,
+}
+
+#[derive(Deserialize)]
+struct Download {
+    sha1: String,
+    url: String,
+}
+
+pub struct Vanilla;
+
+// Vanilla servers have nowhere to put mods or plugins, so most capabilities are left out
+impl super::Loader for Vanilla {
+    fn name(&self) -> &'static str {
+        "vanilla"
+    }
+
+    fn is_loader_jar(&self, minecraft_version: &str, filename: &str) -> bool {
+        filename == format!("vanilla-{minecraft_version}.jar")
+    }
+
+    fn find_build(&self, minecraft_version: &str, version: &str, _: bool) -> Result<super::Build> {
+        resolve(minecraft_version, version)
+    }
+
+    fn list(&self, minecraft_version: Option<&str>) -> Result<()> {
+        list(minecraft_version)
+    }
+}
+
+// The server jarfile is versioned along with the game, so there is no separate loader version
+pub fn resolve(minecraft_version: &str, version: &str) -> Result<super::Build> {
+    let manifest = get_manifest()?;
+
+    let id = match minecraft_version {
+        "latest" => &manifest.latest.release,
+        "snapshot" => &manifest.latest.snapshot,
+        v => v,
+    };
+
+    if version != "latest" && version != id {
+        return Err(anyhow!(
+            "vanilla server versions always match the Minecraft version, try {id}"
+        ));
+    }
+
+    let entry = manifest
+        .versions
+        .iter()
+        .find(|v| v.id == id)
+        .ok_or_else(|| anyhow!("minecraft version {id} does not exist"))?;
+
+    info!("fetching version info for {id}");
+
+    let server = ureq::get(&entry.url)
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .call()?
+        .into_json::<VersionInfo>()?
+        .downloads
+        .server
+        .ok_or_else(|| anyhow!("Mojang does not provide a server jarfile for {id}"))?;
+
+    println!("vanilla {id}");
+
+    Ok(super::Build {
+        minecraft_version: id.to_string(),
+        version: id.to_string(),
+        url: server.url,
+        file: format!("vanilla-{id}.jar"),
+        checksum: Some(format!("sha1#{}", server.sha1)),
+        entrypoint: None,
+    })
+}
+
+pub fn list(minecraft_version: Option<&str>) -> Result<()> {
+    let manifest = get_manifest()?;
+
+    let versions: Vec<&str> = manifest
+        .versions
+        .iter()
+        .map(|v| v.id.as_str())
+        .filter(|v| minecraft_version.is_none_or(|m| m == *v))
+        .collect();
+
+    if versions.is_empty() {
+        return Err(anyhow!("invalid or unsupported minecraft version"));
+    }
+
+    let recommended = versions.iter().position(|v| *v == manifest.latest.release);
+
+    super::print_listing(&versions, recommended);
+
+    Ok(())
+}
+
+fn get_manifest() -> Result<Manifest> {
+    info!("fetching version manifest");
+
+    Ok(ureq::get(MANIFEST_URL)
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .call()?
+        .into_json()?)
+}
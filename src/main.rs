@@ -4,6 +4,7 @@ use std::env;
 
 use clap::{Parser, Subcommand};
 
+mod java;
 mod loader;
 mod plugin;
 mod server;
@@ -27,19 +28,7 @@ struct Cli {
 enum Commands {
     /// Download a modloader jarfile
     #[clap(alias = "l")]
-    Loader {
-        /// Name of the loader to download
-        #[arg(short, long, value_name = "loader", value_parser = loader::parse)]
-        name: String,
-
-        /// Minecraft version to target
-        #[arg(short, long, default_value = "latest")]
-        minecraft_version: String,
-
-        /// Loader version to target
-        #[arg(short, long, default_value = "latest")]
-        version: String,
-    },
+    Loader(loader::LoaderArgs),
 
     /// Work with plugins and mods
     #[command(subcommand)]
@@ -64,11 +53,7 @@ fn main() -> Result<()> {
     pretty_env_logger::init();
 
     match &cli.command {
-        Some(Commands::Loader {
-            name,
-            minecraft_version,
-            version,
-        }) => loader::fetch(name, minecraft_version, version)?,
+        Some(Commands::Loader(l)) => loader::action(l)?,
         Some(Commands::Plugin(p)) => plugin::action(p)?,
         Some(Commands::Server(s)) => server::action(s)?,
         None => (),
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 
+use super::Incompatible;
 use crate::server::lockfile::Lockfile;
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use log::info;
 use mup::FAKE_USER_AGENT;
 use serde::Deserialize;
@@ -10,8 +11,15 @@ use versions::Versioning;
 
 const BASE_URL: &str = "https://hangar.papermc.io/api/v1";
 
+#[derive(Deserialize)]
+struct Versions {
+    result: Vec<VersionInfo>,
+}
+
 #[derive(Deserialize)]
 struct VersionInfo {
+    name: String,
+    channel: VersionChannel,
     downloads: HashMap<String, Download>,
     #[serde(rename = "pluginDependencies")]
     dependencies: HashMap<String, Vec<HDependency>>,
@@ -19,6 +27,22 @@ struct VersionInfo {
     platform_dependencies: HashMap<String, Vec<String>>,
 }
 
+#[derive(Deserialize)]
+struct VersionChannel {
+    name: String,
+}
+
+impl VersionChannel {
+    // Hangar channels are user-defined, so anything unfamiliar is treated as unstable
+    fn stability(&self) -> super::Channel {
+        match self.name.to_lowercase().as_str() {
+            "release" => super::Channel::Release,
+            "beta" | "snapshot" => super::Channel::Beta,
+            _ => super::Channel::Alpha,
+        }
+    }
+}
+
 #[derive(Deserialize)]
 struct Download {
     #[serde(rename = "fileInfo")]
@@ -44,7 +68,12 @@ struct ProjectInfo {
     name: String,
 }
 
-pub fn fetch(lockfile: &Lockfile, project_id: &str, version: &str) -> Result<super::Info> {
+pub fn fetch(
+    lockfile: &Lockfile,
+    project_id: &str,
+    version: &str,
+    channel: super::Channel,
+) -> Result<super::Info> {
     info!("fetching info of project {project_id}");
 
     let formatted_url = format!("{BASE_URL}/projects/{project_id}");
@@ -55,47 +84,70 @@ pub fn fetch(lockfile: &Lockfile, project_id: &str, version: &str) -> Result<sup
         .into_json()?;
 
     let project_id = project_info.name;
+    let kind = lockfile.loader.kind()?;
+    let loader = kind
+        .hangar_platform()
+        .ok_or_else(|| {
+            Incompatible(format!(
+                "hangar does not provide plugins for {}",
+                lockfile.loader.name
+            ))
+        })?
+        .to_string();
+
+    let proxy = kind.is_proxy();
+
+    let version_info = if version == "latest" {
+        get_latest_version(
+            &project_id,
+            &loader,
+            (!proxy).then_some(lockfile.loader.minecraft_version.as_str()),
+            channel,
+        )?
+    } else {
+        info!("fetching info for {project_id} v{version}");
 
-    let version = if version == "latest" {
-        info!("fetching latest version of project {project_id}");
-
-        let formatted_url = format!("{BASE_URL}/projects/{project_id}/latest");
+        let formatted_url = format!("{BASE_URL}/projects/{project_id}/versions/{version}");
 
         ureq::get(&formatted_url)
             .set("User-Agent", FAKE_USER_AGENT)
-            .query("channel", "Release")
             .call()?
-            .into_string()?
-    } else {
-        version.into()
+            .into_json()?
     };
 
-    info!("fetching info for {project_id} v{version}");
-
-    let formatted_url = format!("{BASE_URL}/projects/{project_id}/versions/{version}");
-
-    let version_info: VersionInfo = ureq::get(&formatted_url)
-        .set("User-Agent", FAKE_USER_AGENT)
-        .call()?
-        .into_json()?;
-
-    let loader = lockfile.loader.name.to_uppercase();
+    let version = version_info.name;
 
     if !version_info.platform_dependencies.contains_key(&loader) {
-        return Err(anyhow!(
+        return Err(Incompatible(format!(
             "plugin version {version} does not support {loader}"
-        ));
+        ))
+        .into());
     }
 
-    let minecraft_version = Versioning::new(&lockfile.loader.minecraft_version).unwrap();
-    let is_compatible = version_info.platform_dependencies[&loader]
-        .iter()
-        // Why this doesn't work without the closure I will never know.
-        .filter_map(|v| Versioning::new(v))
-        .any(|v| v == minecraft_version);
+    let platform_versions = &version_info.platform_dependencies[&loader];
+
+    let is_compatible = if proxy {
+        // Proxy plugins list proxy versions like 3.3 rather than exact builds
+        let proxy_version = &lockfile.loader.minecraft_version;
+        proxy_version == "latest"
+            || platform_versions
+                .iter()
+                .any(|v| matches_proxy_version(proxy_version, v))
+    } else {
+        let minecraft_version = Versioning::new(&lockfile.loader.minecraft_version).unwrap();
+        platform_versions
+            .iter()
+            // Why this doesn't work without the closure I will never know.
+            .filter_map(|v| Versioning::new(v))
+            .any(|v| v == minecraft_version)
+    };
 
     if !is_compatible {
-        return Err(anyhow!("version {version} of {project_id} is incompatible with Minecraft version {minecraft_version}"));
+        return Err(Incompatible(format!(
+            "version {version} of {project_id} is incompatible with {loader} {}",
+            lockfile.loader.minecraft_version
+        ))
+        .into());
     }
 
     let dependencies = if version_info.dependencies.contains_key(&loader) {
@@ -120,7 +172,66 @@ pub fn fetch(lockfile: &Lockfile, project_id: &str, version: &str) -> Result<sup
             version_info.downloads[&loader].file_info.sha256
         )),
         dependencies,
+        channel,
+        pinned: false,
     };
 
     Ok(info)
 }
+
+// Whether a proxy version falls under a platform version, comparing whole components so
+// 3.1 covers 3.1.2 but not 3.10.0-SNAPSHOT
+fn matches_proxy_version(proxy_version: &str, platform_version: &str) -> bool {
+    let mut proxy = proxy_version
+        .split('.')
+        .map(|c| c.split('-').next().unwrap_or_default());
+
+    platform_version.split('.').all(|c| proxy.next() == Some(c))
+}
+
+fn get_latest_version(
+    project_id: &str,
+    loader: &str,
+    minecraft_version: Option<&str>,
+    channel: super::Channel,
+) -> Result<VersionInfo> {
+    info!("fetching latest version of project {project_id}");
+
+    let formatted_url = format!("{BASE_URL}/projects/{project_id}/versions");
+
+    let mut req = ureq::get(&formatted_url)
+        .set("User-Agent", FAKE_USER_AGENT)
+        .query("platform", loader);
+
+    if let Some(minecraft_version) = minecraft_version {
+        req = req.query("platformVersion", minecraft_version);
+    }
+
+    let versions: Versions = req.call()?.into_json()?;
+
+    versions
+        .result
+        .into_iter()
+        .find(|v| channel.accepts(v.channel.stability()))
+        .ok_or_else(|| {
+            Incompatible(format!(
+                "could not find a version of {project_id} on the {channel} channel"
+            ))
+            .into()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches_proxy_version;
+
+    #[test]
+    fn matches_whole_version_components() {
+        assert!(matches_proxy_version("3.3.0-SNAPSHOT", "3.3"));
+        assert!(matches_proxy_version("3.3.0-SNAPSHOT", "3.3.0"));
+        assert!(matches_proxy_version("3.1.2", "3"));
+
+        assert!(!matches_proxy_version("3.10.0-SNAPSHOT", "3.1"));
+        assert!(!matches_proxy_version("3.3", "3.3.0"));
+    }
+}
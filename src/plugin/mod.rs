@@ -1,10 +1,9 @@
-use std::{fs::File, io, path::PathBuf};
+use std::{fmt, fs, path::PathBuf};
 
 use anyhow::{anyhow, Result};
-use clap::Subcommand;
-use log::warn;
+use clap::{Subcommand, ValueEnum};
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
-use sha2::{Sha256, Sha512};
 
 use crate::{loader, server::lockfile::Lockfile};
 
@@ -35,6 +34,10 @@ pub enum Plugin {
         /// Do not install any dependencies
         #[arg(short, long, action)]
         no_deps: bool,
+
+        /// The least stable release channel to accept
+        #[arg(short, long, value_enum, default_value_t = Channel::Release)]
+        channel: Channel,
     },
     /// Remove mods or plugins
     Remove {
@@ -49,6 +52,23 @@ pub enum Plugin {
         #[arg(long, action)]
         remove_orphans: bool,
     },
+    /// Hold mods or plugins at their current version
+    Pin {
+        /// The project ID or slug
+        id: String,
+    },
+    /// Allow held mods or plugins to be updated again
+    Unpin {
+        /// The project ID or slug
+        id: String,
+    },
+    /// List mods or plugins with newer versions available
+    Outdated,
+    /// Update mods or plugins to their latest versions
+    Update {
+        /// The project ID or slug, updates everything if omitted
+        id: Option<String>,
+    },
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -59,20 +79,74 @@ pub struct Info {
     pub dependencies: Vec<Dependency>,
     pub source: String,
     pub checksum: Option<String>,
+    #[serde(default)]
+    pub channel: Channel,
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 impl Info {
-    pub fn get_file_path(&self, l: &str) -> String {
+    pub fn get_file_path(&self, l: &str) -> Result<String> {
         let filename = self.source.rsplit_once('/').unwrap().1;
-        format!("{}/{}", loader::location(l), filename)
+        Ok(format!("{}/{}", location(l)?, filename))
+    }
+
+    pub fn provider(&self) -> Result<&str> {
+        self.source
+            .split_once('#')
+            .map(|(provider, _)| provider)
+            .ok_or_else(|| anyhow!("{} has no provider in its source", self.slug))
     }
 }
 
+/// Release channels, ordered from most to least stable
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Release,
+    Beta,
+    Alpha,
+}
+
+impl Channel {
+    /// Whether a version published on the `other` channel is stable enough
+    pub fn accepts(self, other: Self) -> bool {
+        other <= self
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Release => "release",
+            Self::Beta => "beta",
+            Self::Alpha => "alpha",
+        };
+
+        write!(f, "{name}")
+    }
+}
+
+/// A project has no version that runs on the locked loader and Minecraft version,
+/// as opposed to the provider being unreachable or the project not existing
+#[derive(Debug)]
+pub struct Incompatible(pub String);
+
+impl fmt::Display for Incompatible {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for Incompatible {}
+
 #[derive(Clone, Deserialize, Serialize)]
 pub struct Dependency {
     #[serde(alias = "project_id")]
     pub id: String,
-    #[serde(skip)]
     pub required: bool,
 }
 
@@ -90,26 +164,47 @@ pub fn action(plugin: &Plugin) -> Result<()> {
             version,
             optional_deps,
             no_deps,
+            channel,
         } => {
             let provider = provider.as_ref().unwrap();
             let version = version.as_ref().unwrap();
 
-            add(provider, id, version, *optional_deps, *no_deps)?;
+            add(provider, id, version, *channel, *optional_deps, *no_deps)?;
         }
         Plugin::Remove {
             id,
             keep_jarfile,
             remove_orphans,
         } => remove(id, *keep_jarfile, *remove_orphans)?,
+        Plugin::Pin { id } => set_pinned(id, true)?,
+        Plugin::Unpin { id } => set_pinned(id, false)?,
+        Plugin::Outdated => outdated()?,
+        Plugin::Update { id } => update(id.as_deref())?,
     }
 
     Ok(())
 }
 
+/// Resolve a project version which can run on the lockfile's loader and Minecraft version
+pub fn fetch(
+    lockfile: &Lockfile,
+    provider: &str,
+    project_id: &str,
+    version: &str,
+    channel: Channel,
+) -> Result<Info> {
+    match provider {
+        "modrinth" => modrinth::fetch(lockfile, project_id, version, channel),
+        "hangar" => hangar::fetch(lockfile, project_id, version, channel),
+        _ => Err(anyhow!("unknown provider {provider}")),
+    }
+}
+
 pub fn add(
     provider: &str,
     project_id: &str,
     version: &str,
+    channel: Channel,
     optional_deps: bool,
     no_deps: bool,
 ) -> Result<()> {
@@ -121,11 +216,29 @@ pub fn add(
         ));
     }
 
-    let info: Result<Info> = match provider {
-        "modrinth" => modrinth::fetch(&lockfile, project_id, version),
-        "hangar" => hangar::fetch(&lockfile, project_id, version),
-        _ => unimplemented!(),
-    };
+    location(&lockfile.loader.name)?;
+
+    add_to(
+        &mut lockfile,
+        provider,
+        project_id,
+        version,
+        channel,
+        optional_deps,
+        no_deps,
+    )
+}
+
+fn add_to(
+    lockfile: &mut Lockfile,
+    provider: &str,
+    project_id: &str,
+    version: &str,
+    channel: Channel,
+    optional_deps: bool,
+    no_deps: bool,
+) -> Result<()> {
+    let info = fetch(lockfile, provider, project_id, version, channel);
 
     if let Some(error) = info.as_ref().err() {
         if &error.to_string() == "client side" {
@@ -147,7 +260,7 @@ pub fn add(
             continue;
         }
 
-        add(provider, &dep.id, "latest", false, false)?;
+        add_to(lockfile, provider, &dep.id, "latest", channel, false, false)?;
     }
 
     download(&info.source, &lockfile.loader.name, info.checksum.as_ref())?;
@@ -167,26 +280,135 @@ fn remove(id: &str, keep_jarfile: bool, remove_orphans: bool) -> Result<()> {
     lockfile.remove(id, keep_jarfile, remove_orphans)
 }
 
-pub fn download(source: &str, loader_name: &str, checksum: Option<&String>) -> Result<()> {
-    let filename = source.rsplit_once('/').unwrap().1;
-    let file_path = format!("{}/{}", loader::location(loader_name), filename);
+fn set_pinned(id: &str, pinned: bool) -> Result<()> {
+    let mut lockfile = Lockfile::init()?;
 
-    let source = source.split_once('#').unwrap().1;
+    if !lockfile.is_initialized() {
+        return Err(anyhow!(
+            "you must initialize a server before modifying projects"
+        ));
+    }
+
+    lockfile.get_mut(id)?.pinned = pinned;
 
-    if checksum.is_none() {
-        let resp = ureq::get(source)
-            .set("User-Agent", mup::FAKE_USER_AGENT)
-            .call()?;
+    lockfile.save()
+}
+
+fn outdated() -> Result<()> {
+    let lockfile = Lockfile::init()?;
 
-        let mut file = File::create(&file_path)?;
-        io::copy(&mut resp.into_reader(), &mut file)?;
+    if !lockfile.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
     }
 
-    let (method, hash) = checksum.unwrap().split_once('#').unwrap();
+    for entry in &lockfile.plugins {
+        if entry.pinned {
+            println!("{} {} (held)", entry.slug, entry.version);
+            continue;
+        }
+
+        let latest = entry
+            .provider()
+            .and_then(|provider| fetch(&lockfile, provider, &entry.id, "latest", entry.channel));
 
-    match method {
-        "sha512" => mup::download_with_checksum::<Sha512>(source, &PathBuf::from(file_path), hash),
-        "sha256" => mup::download_with_checksum::<Sha256>(source, &PathBuf::from(file_path), hash),
-        _ => unimplemented!(),
+        match latest {
+            Ok(latest) if latest.version != entry.version => {
+                println!("{} {} -> {}", entry.slug, entry.version, latest.version);
+            }
+            Ok(_) => {}
+            Err(e) => println!("{} {} (error: {e})", entry.slug, entry.version),
+        }
     }
+
+    Ok(())
+}
+
+fn update(id: Option<&str>) -> Result<()> {
+    let mut lockfile = Lockfile::init()?;
+
+    if !lockfile.is_initialized() {
+        return Err(anyhow!(
+            "you must initialize a server before modifying projects"
+        ));
+    }
+
+    let slugs: Vec<String> = match id {
+        Some(id) => vec![lockfile.get(id)?.slug.clone()],
+        None => lockfile.plugins.iter().map(|p| p.slug.clone()).collect(),
+    };
+
+    let mut failed = 0;
+
+    for slug in slugs {
+        if let Err(e) = update_one(&mut lockfile, &slug) {
+            println!("{slug} (error: {e})");
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(anyhow!("{failed} projects could not be updated"));
+    }
+
+    Ok(())
+}
+
+fn update_one(lockfile: &mut Lockfile, slug: &str) -> Result<()> {
+    let entry = lockfile.get(slug)?;
+
+    if entry.pinned {
+        println!("{slug} {} (held)", entry.version);
+        return Ok(());
+    }
+
+    let latest = fetch(
+        lockfile,
+        entry.provider()?,
+        &entry.id,
+        "latest",
+        entry.channel,
+    )?;
+
+    if latest.version == entry.version {
+        info!("{slug} is up to date");
+        return Ok(());
+    }
+
+    println!("{slug} {} -> {}", entry.version, latest.version);
+
+    // Only drop the old jar once the new one has been downloaded and verified
+    let old_path = entry.get_file_path(&lockfile.loader.name)?;
+    let new_path = latest.get_file_path(&lockfile.loader.name)?;
+
+    download(
+        &latest.source,
+        &lockfile.loader.name,
+        latest.checksum.as_ref(),
+    )?;
+
+    if old_path != new_path && PathBuf::from(&old_path).exists() {
+        fs::remove_file(old_path)?;
+    }
+
+    *lockfile.get_mut(slug)? = latest;
+    lockfile.save()
+}
+
+pub fn download(source: &str, loader_name: &str, checksum: Option<&String>) -> Result<()> {
+    let filename = source.rsplit_once('/').unwrap().1;
+    let file_path = format!("{}/{}", location(loader_name)?, filename);
+
+    let source = source.split_once('#').unwrap().1;
+
+    mup::download(
+        source,
+        &PathBuf::from(file_path),
+        checksum.map(String::as_str),
+    )
+}
+
+fn location(loader_name: &str) -> Result<&'static str> {
+    loader::get(loader_name)?
+        .location()
+        .ok_or_else(|| anyhow!("{loader_name} servers cannot run mods or plugins"))
 }
@@ -5,6 +5,7 @@ use log::info;
 use mup::FAKE_USER_AGENT;
 use serde::Deserialize;
 
+use super::Incompatible;
 use crate::server::lockfile::Lockfile;
 
 const BASE_URL: &str = "https://api.modrinth.com/v2";
@@ -13,12 +14,20 @@ const BASE_URL: &str = "https://api.modrinth.com/v2";
 pub struct Version {
     pub id: String,
     pub project_id: String,
-    pub dependencies: Vec<super::Dependency>,
+    pub dependencies: Vec<Dependency>,
+    #[serde(rename = "version_type")]
+    channel: super::Channel,
     game_versions: Vec<String>,
     loaders: Vec<String>,
     files: Vec<ProjectFile>,
 }
 
+#[derive(Clone, Deserialize)]
+pub struct Dependency {
+    pub project_id: Option<String>,
+    dependency_type: String,
+}
+
 #[derive(Clone, Deserialize)]
 pub struct ProjectFile {
     pub hashes: Hashes,
@@ -41,7 +50,12 @@ pub struct ProjectInfo {
     versions: Vec<String>,
 }
 
-pub fn fetch(lockfile: &Lockfile, id: &str, version: &str) -> Result<super::Info> {
+pub fn fetch(
+    lockfile: &Lockfile,
+    id: &str,
+    version: &str,
+    channel: super::Channel,
+) -> Result<super::Info> {
     let formatted_url = format!("{BASE_URL}/project/{id}");
 
     info!("Fetching project info for {id}");
@@ -55,50 +69,38 @@ pub fn fetch(lockfile: &Lockfile, id: &str, version: &str) -> Result<super::Info
         return Err(anyhow!("client side"));
     }
 
-    if !project_info.loaders.contains(&lockfile.loader.name) {
-        return Err(anyhow!(
+    let kind = lockfile.loader.kind()?;
+    let loaders = kind.modrinth_loaders();
+
+    if !supports(&project_info.loaders, loaders) {
+        return Err(Incompatible(format!(
             "project {id} does not support {}",
             lockfile.loader.name
-        ));
+        ))
+        .into());
     }
 
-    if !project_info
-        .game_versions
-        .contains(&lockfile.loader.minecraft_version)
-    {
-        return Err(anyhow!(
-            "project does not support Minecraft version {}",
-            lockfile.loader.minecraft_version
-        ));
+    // Proxies run plugins for every Minecraft version they can proxy, and
+    // their own version is not something Modrinth tracks
+    let minecraft_version = (!kind.is_proxy()).then_some(&lockfile.loader.minecraft_version);
+
+    if let Some(minecraft_version) = minecraft_version {
+        if !project_info.game_versions.contains(minecraft_version) {
+            return Err(Incompatible(format!(
+                "project does not support Minecraft version {minecraft_version}"
+            ))
+            .into());
+        }
     }
 
     if version != "latest" && !project_info.versions.contains(&version.to_string()) {
         return Err(anyhow!("project version {version} does not exist"));
     }
 
-    if !project_info
-        .game_versions
-        .contains(&lockfile.loader.minecraft_version)
-    {
-        return Err(anyhow!(
-            "project does not support minecraft version {}",
-            lockfile.loader.minecraft_version
-        ));
-    }
-
     let version_info = if version == "latest" {
-        get_latest_version(
-            &project_info.slug,
-            &lockfile.loader.minecraft_version,
-            &lockfile.loader.name,
-        )?
+        get_latest_version(&project_info.slug, minecraft_version, loaders, channel)?
     } else {
-        get_specific_version(
-            &project_info.slug,
-            version,
-            &lockfile.loader.minecraft_version,
-            &lockfile.loader.name,
-        )?
+        get_specific_version(&project_info.slug, version, minecraft_version, loaders)?
     };
 
     let project_file = version_info
@@ -116,11 +118,18 @@ pub fn fetch(lockfile: &Lockfile, id: &str, version: &str) -> Result<super::Info
         dependencies: version_info
             .dependencies
             .iter()
-            .map(|d| super::Dependency {
-                id: d.id.clone(),
-                required: d.required,
+            // Embedded and incompatible dependencies are never installed,
+            // and dependencies on a bare version ID have no project to track
+            .filter(|d| matches!(d.dependency_type.as_str(), "required" | "optional"))
+            .filter_map(|d| {
+                Some(super::Dependency {
+                    id: d.project_id.clone()?,
+                    required: d.dependency_type == "required",
+                })
             })
             .collect(),
+        channel,
+        pinned: false,
     };
 
     Ok(info)
@@ -129,8 +138,8 @@ pub fn fetch(lockfile: &Lockfile, id: &str, version: &str) -> Result<super::Info
 fn get_specific_version(
     slug: &str,
     version: &str,
-    minecraft_version: &String,
-    loader: &String,
+    minecraft_version: Option<&String>,
+    loaders: &[&str],
 ) -> Result<Version> {
     let formatted_url = format!("{BASE_URL}/version/{version}");
 
@@ -148,33 +157,44 @@ fn get_specific_version(
         ));
     }
 
-    if !resp.game_versions.contains(minecraft_version) {
-        return Err(anyhow!(
-            "version id {version} does not support Minecraft version {minecraft_version}"
-        ));
+    if let Some(minecraft_version) = minecraft_version {
+        if !resp.game_versions.contains(minecraft_version) {
+            return Err(Incompatible(format!(
+                "version id {version} does not support Minecraft version {minecraft_version}"
+            ))
+            .into());
+        }
     }
 
-    if !resp.loaders.contains(loader) {
-        return Err(anyhow!(
-            "project version {version} does not support loader {loader}",
-        ));
+    if !supports(&resp.loaders, loaders) {
+        return Err(Incompatible(format!(
+            "project version {version} does not support any of {loaders:?}",
+        ))
+        .into());
     }
 
     Ok(resp)
 }
 
-fn get_latest_version(slug: &str, minecraft_version: &String, loader: &String) -> Result<Version> {
+fn get_latest_version(
+    slug: &str,
+    minecraft_version: Option<&String>,
+    loaders: &[&str],
+    channel: super::Channel,
+) -> Result<Version> {
     let formatted_url = format!("{BASE_URL}/project/{slug}/version");
 
-    let mut req = ureq::get(&formatted_url)
-        .set("User-Agent", FAKE_USER_AGENT)
-        .query(
+    let mut req = ureq::get(&formatted_url).set("User-Agent", FAKE_USER_AGENT);
+
+    if let Some(minecraft_version) = minecraft_version {
+        req = req.query(
             "game_versions",
             format!("[\"{minecraft_version}\"]").as_str(),
         );
+    }
 
-    if !loader.is_empty() {
-        req = req.query("loaders", format!("[\"{loader}\"]").as_str());
+    if !loaders.is_empty() {
+        req = req.query("loaders", &serde_json::to_string(loaders)?);
     }
 
     info!("fetching latest version of {slug}");
@@ -183,15 +203,29 @@ fn get_latest_version(slug: &str, minecraft_version: &String, loader: &String) -
 
     let version = resp
         .iter()
-        .find(|p| p.game_versions.contains(minecraft_version))
-        .ok_or_else(|| anyhow!("could not find a matching version"))?;
-
-    if !version.loaders.contains(loader) {
-        return Err(anyhow!(
-            "project version ID {} does not support loader {loader}",
+        .find(|p| {
+            minecraft_version.is_none_or(|v| p.game_versions.contains(v))
+                && channel.accepts(p.channel)
+        })
+        .ok_or_else(|| {
+            Incompatible(format!(
+                "could not find a matching version on the {channel} channel"
+            ))
+        })?;
+
+    if !supports(&version.loaders, loaders) {
+        return Err(Incompatible(format!(
+            "project version ID {} does not support any of {loaders:?}",
             version.id
-        ));
+        ))
+        .into());
     }
 
     Ok(version.clone())
 }
+
+fn supports(project_loaders: &[String], loaders: &[&str]) -> bool {
+    project_loaders
+        .iter()
+        .any(|l| loaders.contains(&l.as_str()))
+}
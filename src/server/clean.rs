@@ -0,0 +1,130 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use glob::Pattern;
+use versions::Versioning;
+
+use super::lockfile::Lockfile;
+
+const QUARANTINE_PATH: &str = ".mup-quarantine";
+
+pub fn clean(dry_run: bool, keep: &[String], quarantine: bool, loaders: bool) -> Result<()> {
+    let lf = Lockfile::init()?;
+    if !lf.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    let keep = keep
+        .iter()
+        .map(|k| Pattern::new(k))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut candidates = untracked(&lf)?;
+    if loaders {
+        candidates.append(&mut superseded_loaders(&lf)?);
+    }
+
+    for path in candidates {
+        let filename = path.file_name().unwrap().to_string_lossy();
+        if keep.iter().any(|k| k.matches(&filename)) {
+            continue;
+        }
+
+        if dry_run {
+            println!("would remove: {}", path.display());
+        } else if quarantine {
+            let destination = PathBuf::from(QUARANTINE_PATH).join(&path);
+            fs::create_dir_all(destination.parent().unwrap())?;
+            fs::rename(&path, &destination)?;
+
+            println!("quarantined: {}", path.display());
+        } else {
+            fs::remove_file(&path)?;
+
+            println!("removed: {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Jarfiles in the mods or plugins directory which no lockfile entry refers to
+pub fn untracked(lf: &Lockfile) -> Result<Vec<PathBuf>> {
+    let Some(location) = lf.loader.kind()?.location().map(PathBuf::from) else {
+        return Ok(vec![]);
+    };
+
+    if !location.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let tracked = lf
+        .plugins
+        .iter()
+        .map(|p| p.get_file_path(&lf.loader.name).map(PathBuf::from))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut jars = vec![];
+
+    for entry in fs::read_dir(location)? {
+        let path = entry?.path();
+
+        if is_jar(&path) && !tracked.contains(&path) {
+            jars.push(path);
+        }
+    }
+
+    Ok(jars)
+}
+
+// Every loader jarfile except the locked build, or the newest build for the
+// locked Minecraft version if no build has been locked yet
+fn superseded_loaders(lf: &Lockfile) -> Result<Vec<PathBuf>> {
+    let kind = lf.loader.kind()?;
+    let prefix = format!("{}-", kind.name());
+
+    let mut jars = vec![];
+
+    for entry in fs::read_dir(".")? {
+        let path = PathBuf::from(entry?.file_name());
+        let filename = path.file_name().unwrap().to_string_lossy();
+
+        if is_jar(&path) && filename.starts_with(&prefix) {
+            jars.push(path);
+        }
+    }
+
+    let build_of = |path: &PathBuf| {
+        let stem = path.file_stem().unwrap().to_string_lossy();
+        stem.rsplit_once('-')
+            .and_then(|(_, build)| Versioning::new(build))
+    };
+
+    // Installed loaders are launched from whatever their installer produced
+    if let Some(file) = &lf.loader.file {
+        let entrypoint = lf.loader.entrypoint.as_deref().unwrap_or(file);
+        jars.retain(|p| p != Path::new(file) && p != Path::new(entrypoint));
+        return Ok(jars);
+    }
+
+    let current = jars
+        .iter()
+        .filter(|p| {
+            let filename = p.file_name().unwrap().to_string_lossy();
+            kind.is_loader_jar(&lf.loader.minecraft_version, &filename)
+        })
+        .max_by_key(|p| build_of(p))
+        .cloned();
+
+    jars.retain(|p| Some(p) != current.as_ref());
+
+    Ok(jars)
+}
+
+fn is_jar(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("jar"))
+}
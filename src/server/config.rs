@@ -0,0 +1,414 @@
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use log::{info, warn};
+
+use super::lockfile::Lockfile;
+
+const PROPERTIES_PATH: &str = "server.properties";
+
+const BOOLEAN_KEYS: [&str; 26] = [
+    "accepts-transfers",
+    "allow-flight",
+    "allow-nether",
+    "broadcast-console-to-ops",
+    "broadcast-rcon-to-ops",
+    "enable-command-block",
+    "enable-jmx-monitoring",
+    "enable-query",
+    "enable-rcon",
+    "enable-status",
+    "enforce-secure-profile",
+    "enforce-whitelist",
+    "force-gamemode",
+    "generate-structures",
+    "hardcore",
+    "hide-online-players",
+    "log-ips",
+    "online-mode",
+    "prevent-proxy-connections",
+    "pvp",
+    "require-resource-pack",
+    "spawn-animals",
+    "spawn-monsters",
+    "spawn-npcs",
+    "sync-chunk-writes",
+    "white-list",
+];
+
+#[derive(Debug, Subcommand)]
+pub enum Config {
+    /// Print the value of a property
+    Get { key: String },
+
+    /// Change the value of a property
+    Set {
+        key: String,
+        value: String,
+
+        /// Also record the property in the lockfile, so install applies it
+        #[arg(short, long, action)]
+        lock: bool,
+    },
+
+    /// Print every property, marking those recorded in the lockfile
+    List,
+}
+
+pub fn action(config: &Config) -> Result<()> {
+    match config {
+        Config::Get { key } => {
+            let properties = Properties::load()?;
+            let value = properties
+                .get(key)
+                .ok_or_else(|| anyhow!("{key} is not set in {PROPERTIES_PATH}"))?;
+
+            println!("{value}");
+
+            Ok(())
+        }
+        Config::Set { key, value, lock } => set(key, value, *lock),
+        Config::List => list(),
+    }
+}
+
+fn set(key: &str, value: &str, lock: bool) -> Result<()> {
+    validate(key, value)?;
+
+    let mut lf = Lockfile::init()?;
+    if !lf.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    if lock {
+        lf.properties.insert(key.to_string(), value.to_string());
+        lf.save()?;
+    } else if lf.properties.get(key).is_some_and(|v| v != value) {
+        warn!("{key} is locked to a different value, install will change it back");
+    }
+
+    let mut properties = Properties::load()?;
+    properties.set(key, value);
+    properties.save()
+}
+
+fn list() -> Result<()> {
+    let lf = Lockfile::init()?;
+    let properties = Properties::load()?;
+
+    for (key, value) in properties.iter() {
+        if lf.properties.contains_key(key) {
+            println!("{key}={value} (locked)");
+        } else {
+            println!("{key}={value}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the properties declared in the lockfile to server.properties
+pub fn apply(declared: &BTreeMap<String, String>) -> Result<()> {
+    if declared.is_empty() {
+        return Ok(());
+    }
+
+    let mut properties = Properties::load()?;
+
+    for (key, value) in declared {
+        validate(key, value)?;
+        properties.set(key, value);
+    }
+
+    properties.save()
+}
+
+/// Reject values the server would refuse or silently replace, for the keys mup knows about
+pub fn validate(key: &str, value: &str) -> Result<()> {
+    let valid = match key {
+        "server-port" | "query.port" | "rcon.port" => value.parse::<u16>().is_ok_and(|p| p > 0),
+        "gamemode" => matches!(
+            value,
+            "survival" | "creative" | "adventure" | "spectator" | "0" | "1" | "2" | "3"
+        ),
+        "difficulty" => matches!(
+            value,
+            "peaceful" | "easy" | "normal" | "hard" | "0" | "1" | "2" | "3"
+        ),
+        "max-players" | "spawn-protection" | "max-tick-time" => value.parse::<i64>().is_ok(),
+        "view-distance" | "simulation-distance" => {
+            value.parse::<u8>().is_ok_and(|d| (3..=32).contains(&d))
+        }
+        "op-permission-level" => value.parse::<u8>().is_ok_and(|l| l <= 4),
+        "function-permission-level" => value.parse::<u8>().is_ok_and(|l| (1..=4).contains(&l)),
+        "max-world-size" => value
+            .parse::<u32>()
+            .is_ok_and(|s| (1..=29_999_984).contains(&s)),
+        k if BOOLEAN_KEYS.contains(&k) => matches!(value, "true" | "false"),
+        _ => true,
+    };
+
+    if !valid {
+        return Err(anyhow!("{value} is not a valid value for {key}"));
+    }
+
+    Ok(())
+}
+
+// Comments, blank lines, order and untouched properties are kept exactly as they were
+enum Line {
+    Property {
+        key: String,
+        /// Unescaped, as the server reads it
+        value: String,
+        /// The line as it was read, until the property is changed
+        raw: Option<String>,
+    },
+    Other(String),
+}
+
+/// The contents of server.properties
+pub struct Properties {
+    lines: Vec<Line>,
+}
+
+impl Properties {
+    pub fn load() -> Result<Self> {
+        if !Path::new(PROPERTIES_PATH).exists() {
+            return Ok(Self::parse(""));
+        }
+
+        Ok(Self::parse(&fs::read_to_string(PROPERTIES_PATH)?))
+    }
+
+    fn parse(contents: &str) -> Self {
+        let lines = contents
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+
+                if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                    return Line::Other(line.to_string());
+                }
+
+                let (key, value) = split_property(trimmed);
+
+                Line::Property {
+                    key: unescape(key.trim_end()),
+                    value: unescape(value.trim_start()),
+                    raw: Some(line.to_string()),
+                }
+            })
+            .collect();
+
+        Self { lines }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.iter().find(|(k, _)| *k == key).map(|(_, v)| v)
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        for line in &mut self.lines {
+            if let Line::Property {
+                key: k,
+                value: v,
+                raw,
+            } = line
+            {
+                if k == key {
+                    if v != value {
+                        value.clone_into(v);
+                        *raw = None;
+                    }
+
+                    return;
+                }
+            }
+        }
+
+        self.lines.push(Line::Property {
+            key: key.to_string(),
+            value: value.to_string(),
+            raw: None,
+        });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Property { key, value, .. } => Some((key.as_str(), value.as_str())),
+            Line::Other(_) => None,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        info!("writing {PROPERTIES_PATH}");
+
+        fs::write(PROPERTIES_PATH, self.to_string())?;
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Properties {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Property { raw: Some(raw), .. } | Line::Other(raw) => writeln!(f, "{raw}")?,
+                Line::Property { key, value, .. } => {
+                    writeln!(f, "{}={}", escape(key), escape(value))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Split a property line at the first unescaped = or :
+fn split_property(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '=' | ':' => return (&line[..i], &line[i + 1..]),
+            _ => {}
+        }
+    }
+
+    (line, "")
+}
+
+// Undo the escapes Java's Properties writes, such as https\://
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('f') => unescaped.push('\u{c}'),
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+
+                if let Some(c) = u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                    unescaped.push(c);
+                }
+            }
+            Some(c) => unescaped.push(c),
+            None => {}
+        }
+    }
+
+    unescaped
+}
+
+// Escape text the same way Java's Properties does when the server saves it
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\\' | '=' | ':' | '#' | '!' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' ' if i == 0 => escaped.push_str("\\ "),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{c}' => escaped.push_str("\\f"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate, Properties};
+
+    const PROPERTIES: &str = "\
+#Minecraft server properties
+#Sat Oct 17 12:00:00 UTC 2026
+motd=A Minecraft Server
+
+server-port=25565
+level-seed=
+";
+
+    #[test]
+    fn round_trips_comments_and_order() {
+        assert_eq!(Properties::parse(PROPERTIES).to_string(), PROPERTIES);
+    }
+
+    #[test]
+    fn set_replaces_in_place_or_appends() {
+        let mut properties = Properties::parse(PROPERTIES);
+
+        properties.set("server-port", "25566");
+        properties.set("white-list", "true");
+
+        assert_eq!(properties.get("server-port"), Some("25566"));
+        assert_eq!(properties.get("level-seed"), Some(""));
+        assert_eq!(
+            properties.to_string(),
+            PROPERTIES.replace("25565", "25566") + "white-list=true\n"
+        );
+    }
+
+    #[test]
+    fn unescapes_and_escapes_values() {
+        let contents = "resource-pack=https\\://example.com/pack.zip\nrcon.password=p\\=ss\\#1\n";
+        let mut properties = Properties::parse(contents);
+
+        assert_eq!(
+            properties.get("resource-pack"),
+            Some("https://example.com/pack.zip")
+        );
+        assert_eq!(properties.get("rcon.password"), Some("p=ss#1"));
+
+        // Setting the same value keeps the line as it was
+        properties.set("rcon.password", "p=ss#1");
+        assert_eq!(properties.to_string(), contents);
+
+        properties.set("resource-pack", "https://example.com/other.zip");
+        assert!(properties
+            .to_string()
+            .starts_with("resource-pack=https\\://example.com/other.zip\n"));
+    }
+
+    #[test]
+    fn keeps_untouched_lines_as_they_were() {
+        let contents = "motd = A Minecraft Server\nmax-players: 20\n";
+
+        assert_eq!(Properties::parse(contents).to_string(), contents);
+    }
+
+    #[test]
+    fn validates_boundaries() {
+        assert!(validate("server-port", "0").is_err());
+        assert!(validate("server-port", "1").is_ok());
+        assert!(validate("server-port", "65535").is_ok());
+        assert!(validate("server-port", "65536").is_err());
+
+        assert!(validate("view-distance", "2").is_err());
+        assert!(validate("view-distance", "3").is_ok());
+        assert!(validate("view-distance", "32").is_ok());
+        assert!(validate("view-distance", "33").is_err());
+
+        assert!(validate("pvp", "yes").is_err());
+        assert!(validate("some-plugin-key", "anything").is_ok());
+    }
+}
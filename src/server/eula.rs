@@ -1,21 +1,91 @@
 use std::{
-    fs::{self, File},
-    io::Write,
+    env, fs,
+    io::{self, BufRead, IsTerminal, Write},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use log::{info, warn};
 
-pub fn sign() -> Result<()> {
-    let mut file = if fs::metadata("eula.txt").is_err() {
-        File::create("eula.txt")?
-    } else {
-        fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open("eula.txt")?
+use super::lockfile::{Eula, Lockfile};
+
+const EULA_URL: &str = "https://aka.ms/MinecraftEULA";
+const EULA_PATH: &str = "eula.txt";
+
+/// Accept the EULA on the user's behalf and record that they did
+pub fn accept() -> Result<()> {
+    let mut lf = Lockfile::init()?;
+    if !lf.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    record(&mut lf)?;
+    sign(&lf)
+}
+
+/// Sign eula.txt if the EULA was accepted, asking for acceptance if it wasn't
+pub fn ensure(lf: &mut Lockfile, accept_eula: bool) -> Result<()> {
+    // Proxies don't run Minecraft itself, so they have no EULA to accept
+    if lf.loader.kind()?.is_proxy() {
+        return Ok(());
+    }
+
+    if lf.eula.is_none() {
+        if !accept_eula && !prompt()? {
+            println!("the EULA was not accepted, the server will not start until it is");
+            println!("read it at {EULA_URL}, then run mup server sign to accept it");
+
+            return Ok(());
+        }
+
+        record(lf)?;
+    }
+
+    sign(lf)
+}
+
+fn prompt() -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        warn!("not asking to accept the EULA without a terminal, pass --accept-eula instead");
+        return Ok(false);
+    }
+
+    print!("Do you accept the Minecraft EULA ({EULA_URL})? [y/N] ");
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+fn record(lf: &mut Lockfile) -> Result<()> {
+    let accepted_by = env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_else(|_| String::from("unknown"));
+
+    lf.eula = Some(Eula {
+        accepted_by,
+        accepted_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+    });
+
+    lf.save()
+}
+
+fn sign(lf: &Lockfile) -> Result<()> {
+    let Some(eula) = &lf.eula else {
+        return Ok(());
     };
 
-    file.write_all(b"# Signed by mup\neula=true")?;
+    info!("signing {EULA_PATH}");
+
+    fs::write(
+        EULA_PATH,
+        format!(
+            "# Accepted by {} through mup, see {EULA_URL}\neula=true\n",
+            eula.accepted_by
+        ),
+    )?;
 
     Ok(())
 }
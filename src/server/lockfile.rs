@@ -1,22 +1,38 @@
 use std::{
-    fs::{self, File},
+    collections::BTreeMap,
+    fs::{self, File, TryLockError},
     io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use anyhow::{anyhow, Result};
-use log::info;
+use clap::ValueEnum;
+use log::{info, warn};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use versions::Versioning;
 
-use crate::{loader, plugin};
+use super::{migrate, players, scripts};
+use crate::{java, loader, plugin};
 
 const LOCKFILE_PATH: &str = "mup.lock";
+const GUARD_PATH: &str = ".mup.lck";
 
-#[derive(Deserialize, Default, Serialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Lockfile {
+    pub schema_version: u64,
     pub loader: Loader,
+    pub jvm: Jvm,
+    pub scripts: Option<Scripts>,
+    /// Applied to server.properties by install
+    pub properties: BTreeMap<String, String>,
+    pub eula: Option<Eula>,
+    /// Applied to the whitelist, ops and bans by install
+    pub players: players::Declared,
     pub plugins: Vec<plugin::Info>,
+    // Held for as long as the lockfile is loaded so concurrent runs can't clobber each other
+    #[serde(skip)]
+    guard: Option<File>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -24,6 +40,10 @@ pub struct Loader {
     pub name: String,
     pub minecraft_version: String,
     pub version: String,
+    pub url: Option<String>,
+    pub file: Option<String>,
+    pub checksum: Option<String>,
+    pub entrypoint: Option<String>,
 }
 
 impl Default for Loader {
@@ -32,27 +52,245 @@ impl Default for Loader {
             name: String::default(),
             minecraft_version: String::from("latest"),
             version: String::from("latest"),
+            url: None,
+            file: None,
+            checksum: None,
+            entrypoint: None,
         }
     }
 }
 
-impl Lockfile {
-    pub fn init() -> Result<Self> {
-        if PathBuf::from(LOCKFILE_PATH).exists() {
-            let mut current_lockfile = File::open(LOCKFILE_PATH)?;
+impl Loader {
+    /// The loader this server runs on
+    pub fn kind(&self) -> Result<&'static dyn loader::Loader> {
+        loader::get(&self.name)
+    }
+
+    /// The Java major version the server needs, if it can be told
+    pub fn required_java(&self) -> Option<u32> {
+        // Proxies are versioned on their own, and don't run Minecraft itself
+        if self.kind().is_ok_and(loader::Loader::is_proxy) {
+            return None;
+        }
+
+        java::required_version(&self.minecraft_version)
+    }
 
-            let mut contents = String::new();
-            current_lockfile.read_to_string(&mut contents)?;
+    /// The locked build, if the loader has been resolved to one
+    pub fn build(&self) -> Option<loader::Build> {
+        Some(loader::Build {
+            minecraft_version: self.minecraft_version.clone(),
+            version: self.version.clone(),
+            url: self.url.clone()?,
+            file: self.file.clone()?,
+            checksum: self.checksum.clone(),
+            entrypoint: self.entrypoint.clone(),
+        })
+    }
+
+    pub fn lock(&mut self, build: loader::Build) {
+        self.minecraft_version = build.minecraft_version;
+        self.version = build.version;
+        self.url = Some(build.url);
+        self.file = Some(build.file);
+        self.checksum = build.checksum;
+        self.entrypoint = build.entrypoint;
+    }
+}
+
+/// How the server's JVM is launched
+#[derive(Deserialize, Serialize)]
+pub struct Jvm {
+    /// Passed to both -Xms and -Xmx, so the heap never has to grow
+    pub heap: String,
+    pub flags: Flags,
+}
 
-            return Ok(serde_json::from_str(&contents)?);
+impl Default for Jvm {
+    fn default() -> Self {
+        Self {
+            heap: String::from("2G"),
+            flags: Flags::default(),
         }
+    }
+}
+
+impl Jvm {
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec![format!("-Xms{}", self.heap), format!("-Xmx{}", self.heap)];
+        args.extend(self.flags.args().iter().map(ToString::to_string));
+
+        args
+    }
+
+    /// Size of a heap setting in megabytes, if it is one the JVM accepts, such as 4G or 512m
+    pub fn heap_megabytes(heap: &str) -> Option<u64> {
+        let split = heap
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(heap.len());
+        let (size, unit) = heap.split_at(split);
+        let size: u64 = size.parse().ok()?;
+
+        // Without a unit the size is in bytes
+        let megabytes = match unit.to_ascii_uppercase().as_str() {
+            "" => size / 1024 / 1024,
+            "K" => size / 1024,
+            "M" => size,
+            "G" => size * 1024,
+            "T" => size * 1024 * 1024,
+            _ => return None,
+        };
+
+        Some(megabytes)
+    }
+}
 
-        File::create(LOCKFILE_PATH)?;
+pub fn parse_heap(input: &str) -> Result<String> {
+    Jvm::heap_megabytes(input)
+        .map(|_| input.to_string())
+        .ok_or_else(|| {
+            anyhow!("{input} is not a heap size, use a number with an optional K, M, G or T suffix")
+        })
+}
 
-        Ok(Self {
+/// Who accepted Minecraft's EULA for this server, and when
+#[derive(Deserialize, Serialize)]
+pub struct Eula {
+    pub accepted_by: String,
+    /// Seconds since the Unix epoch
+    pub accepted_at: u64,
+}
+
+/// Which launch scripts `server scripts` keeps up to date
+#[derive(Deserialize, Serialize)]
+pub struct Scripts {
+    /// Where the server lives on the machine running the systemd unit
+    pub directory: String,
+    pub docker: bool,
+}
+
+/// Curated sets of JVM flags
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Flags {
+    /// Only the heap size
+    None,
+    /// Aikar's G1 tuning, see <https://docs.papermc.io/paper/aikars-flags>
+    #[default]
+    Aikar,
+}
+
+impl Flags {
+    const fn args(self) -> &'static [&'static str] {
+        match self {
+            Self::None => &[],
+            Self::Aikar => &[
+                "-XX:+UseG1GC",
+                "-XX:+ParallelRefProcEnabled",
+                "-XX:MaxGCPauseMillis=200",
+                "-XX:+UnlockExperimentalVMOptions",
+                "-XX:+DisableExplicitGC",
+                "-XX:+AlwaysPreTouch",
+                "-XX:G1NewSizePercent=30",
+                "-XX:G1MaxNewSizePercent=40",
+                "-XX:G1HeapRegionSize=8M",
+                "-XX:G1ReservePercent=20",
+                "-XX:G1HeapWastePercent=5",
+                "-XX:G1MixedGCCountTarget=4",
+                "-XX:InitiatingHeapOccupancyPercent=15",
+                "-XX:G1MixedGCLiveThresholdPercent=90",
+                "-XX:G1RSetUpdatingPauseIntervalMillis=5",
+                "-XX:SurvivorRatio=32",
+                "-XX:+PerfDisableSharedMem",
+                "-XX:MaxTenuringThreshold=1",
+                "-Dusing.aikars.flags=https://mcflags.emc.gs",
+                "-Daikars.new.flags=true",
+            ],
+        }
+    }
+}
+
+impl Default for Lockfile {
+    fn default() -> Self {
+        Self {
+            schema_version: migrate::SCHEMA_VERSION,
             loader: Loader::default(),
+            jvm: Jvm::default(),
+            scripts: None,
+            properties: BTreeMap::new(),
+            eula: None,
+            players: players::Declared::default(),
             plugins: vec![],
-        })
+            guard: None,
+        }
+    }
+}
+
+impl Lockfile {
+    pub fn init() -> Result<Self> {
+        // Nothing is created in directories without a lockfile until something is saved
+        if !PathBuf::from(LOCKFILE_PATH).exists() {
+            return Ok(Self::default());
+        }
+
+        let guard = acquire_guard()?;
+
+        let mut current_lockfile = File::open(LOCKFILE_PATH)?;
+
+        let mut contents = String::new();
+        current_lockfile.read_to_string(&mut contents)?;
+
+        Self::load(&contents, guard)
+    }
+
+    fn load(contents: &str, guard: File) -> Result<Self> {
+        let (mut lf, migrated) = Self::parse(contents, Path::new(LOCKFILE_PATH))?;
+        lf.guard = Some(guard);
+
+        if migrated {
+            lf.save()?;
+        }
+
+        Ok(lf)
+    }
+
+    /// Deserialize a lockfile, migrating it to the current schema if it is older and backing
+    /// up the original next to `path` first. Also returns whether it was migrated.
+    fn parse(contents: &str, path: &Path) -> Result<(Self, bool)> {
+        let value: Value = serde_json::from_str(contents)?;
+
+        // Lockfiles written before versioning was introduced have no schema_version
+        let version = value
+            .get("schema_version")
+            .and_then(Value::as_u64)
+            .unwrap_or(1);
+
+        if version == 0 {
+            return Err(anyhow!(
+                "lockfile has schema version 0, which was never written by mup"
+            ));
+        }
+
+        if version > migrate::SCHEMA_VERSION {
+            return Err(anyhow!(
+                "lockfile uses schema version {version}, but this version of mup only supports up to {}, please upgrade mup",
+                migrate::SCHEMA_VERSION
+            ));
+        }
+
+        if version == migrate::SCHEMA_VERSION {
+            return Ok((serde_json::from_value(value)?, false));
+        }
+
+        let backup = format!("{}.v{version}.bak", path.display());
+        fs::copy(path, &backup)?;
+
+        warn!(
+            "migrating lockfile from schema version {version} to {}, a backup was saved to {backup}",
+            migrate::SCHEMA_VERSION
+        );
+
+        Ok((serde_json::from_value(migrate::run(value, version)?)?, true))
     }
 
     pub fn with_params(minecraft_version: &str, loader: &str) -> Result<Self> {
@@ -64,17 +302,23 @@ impl Lockfile {
             ));
         }
 
+        let kind = loader::get(loader)?;
+        if !kind.supports(minecraft_version) {
+            return Err(anyhow!(
+                "{loader} does not support Minecraft versions before {}",
+                kind.oldest_minecraft_version().unwrap_or_default()
+            ));
+        }
+
         let l = Loader {
             name: loader.to_string(),
             minecraft_version: minecraft_version.to_string(),
-            version: String::from("latest"),
+            ..Loader::default()
         };
 
-        File::create(LOCKFILE_PATH)?;
-
         let mut lf = Self {
             loader: l,
-            plugins: vec![],
+            ..Self::default()
         };
 
         lf.save()?;
@@ -85,7 +329,14 @@ impl Lockfile {
     pub fn get(&self, project_id: &str) -> Result<&plugin::Info> {
         self.plugins
             .iter()
-            .find(|p| p.slug == project_id)
+            .find(|p| p.slug == project_id || p.id == project_id)
+            .ok_or_else(|| anyhow!("key {project_id} not found"))
+    }
+
+    pub fn get_mut(&mut self, project_id: &str) -> Result<&mut plugin::Info> {
+        self.plugins
+            .iter_mut()
+            .find(|p| p.slug == project_id || p.id == project_id)
             .ok_or_else(|| anyhow!("key {project_id} not found"))
     }
 
@@ -105,10 +356,11 @@ impl Lockfile {
         let mut plugins = self.plugins.iter();
 
         let idx = plugins
-            .position(|p| p.slug == slug)
+            .position(|p| p.slug == slug || p.id == slug)
             .ok_or_else(|| anyhow!("{slug} does not exist in the lockfile"))?;
 
         let entry = self.plugins[idx].clone();
+        let entry_slug = entry.slug.clone();
 
         let mut to_remove = vec![entry.slug];
 
@@ -118,7 +370,7 @@ impl Lockfile {
             }
 
             let cant_be_removed = plugins.any(|p| {
-                let is_different = p.slug != slug;
+                let is_different = p.slug != entry_slug;
                 let requires_dep = p.dependencies.iter().any(|d| *d == dep && d.required);
 
                 is_different && requires_dep
@@ -137,7 +389,7 @@ impl Lockfile {
                 .ok_or_else(|| anyhow!("{slug} does not exist in the lockfile"))?;
 
             if !keep_jarfile {
-                fs::remove_file(&self.plugins[idx].get_file_path(&self.loader.name))?;
+                fs::remove_file(self.plugins[idx].get_file_path(&self.loader.name)?)?;
             }
 
             self.plugins.remove(idx);
@@ -148,24 +400,140 @@ impl Lockfile {
         Ok(())
     }
 
-    pub fn is_initialized(&mut self) -> bool {
+    pub fn is_initialized(&self) -> bool {
         let minecraft_version = &self.loader.minecraft_version;
 
         let version = Versioning::new(minecraft_version).unwrap();
 
-        !version.is_complex() && loader::parse(&self.loader.name).is_ok()
+        !version.is_complex()
+            && self
+                .loader
+                .kind()
+                .is_ok_and(|l| l.supports(minecraft_version))
     }
 
     pub fn save(&mut self) -> Result<()> {
         info!("saving transaction to lockfile");
 
-        let mut output = fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(LOCKFILE_PATH)?;
+        if self.guard.is_none() {
+            self.guard = Some(acquire_guard()?);
+        }
 
-        output.write_all(serde_json::to_string(&self)?.as_bytes())?;
+        // Keep diffs stable regardless of the order projects were added in
+        self.plugins.sort_by(|a, b| a.slug.cmp(&b.slug));
 
-        Ok(())
+        let mut contents = serde_json::to_string_pretty(&self)?;
+        contents.push('\n');
+
+        // Write to a temporary file first so a crash can never leave a truncated lockfile
+        let temp_path = format!("{LOCKFILE_PATH}.tmp");
+
+        let mut output = File::create(&temp_path)?;
+        output.write_all(contents.as_bytes())?;
+        output.sync_all()?;
+
+        fs::rename(temp_path, LOCKFILE_PATH)?;
+
+        // Launch scripts are derived from the lockfile, so keep them in step with it
+        scripts::write(self)
+    }
+}
+
+fn acquire_guard() -> Result<File> {
+    let guard = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(GUARD_PATH)?;
+
+    match guard.try_lock() {
+        Ok(()) => (),
+        Err(TryLockError::WouldBlock) => {
+            warn!("waiting for another mup process to finish with the lockfile");
+            guard.lock()?;
+        }
+        Err(TryLockError::Error(e)) => return Err(e.into()),
+    }
+
+    Ok(guard)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf, process};
+
+    use super::{migrate, Jvm, Lockfile};
+
+    const V1: &str = r#"{
+  "loader": { "name": "paper", "minecraft_version": "1.21.1", "version": "latest" },
+  "plugins": []
+}"#;
+
+    // A lockfile path in a directory of its own, so tests can run in parallel
+    fn lockfile_path(test: &str) -> PathBuf {
+        env::temp_dir()
+            .join(format!("mup-{test}-{}", process::id()))
+            .join("mup.lock")
+    }
+
+    #[test]
+    fn backs_up_before_migrating() {
+        let path = lockfile_path("backup");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, V1).unwrap();
+
+        let (lf, migrated) = Lockfile::parse(V1, &path).unwrap();
+
+        assert!(migrated);
+        assert_eq!(lf.schema_version, migrate::SCHEMA_VERSION);
+        assert_eq!(
+            fs::read_to_string(path.with_extension("lock.v1.bak")).unwrap(),
+            V1
+        );
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn loads_current_version_as_is() {
+        let path = lockfile_path("current");
+        let contents = serde_json::to_string(&Lockfile::default()).unwrap();
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &contents).unwrap();
+
+        let (_, migrated) = Lockfile::parse(&contents, &path).unwrap();
+
+        assert!(!migrated);
+        assert!(!path
+            .with_extension(format!("lock.v{}.bak", migrate::SCHEMA_VERSION))
+            .exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        let path = lockfile_path("unknown");
+        let newer = format!(r#"{{ "schema_version": {} }}"#, migrate::SCHEMA_VERSION + 1);
+
+        let error = Lockfile::parse(&newer, &path).err().unwrap();
+        assert!(error.to_string().contains("please upgrade mup"));
+
+        let error = Lockfile::parse(r#"{ "schema_version": 0 }"#, &path)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("schema version 0"));
+    }
+
+    #[test]
+    fn parses_heap_sizes() {
+        assert_eq!(Jvm::heap_megabytes("4G"), Some(4096));
+        assert_eq!(Jvm::heap_megabytes("512m"), Some(512));
+        assert_eq!(Jvm::heap_megabytes("2097152"), Some(2));
+
+        for heap in ["", "G", "4GB", "4.5G", "-4G", "four"] {
+            assert_eq!(Jvm::heap_megabytes(heap), None, "{heap}");
+        }
     }
 }
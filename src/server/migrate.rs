@@ -0,0 +1,183 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+
+/// The lockfile schema version written by this version of mup
+pub const SCHEMA_VERSION: u64 = 8;
+
+type Migration = fn(Map<String, Value>) -> Result<Map<String, Value>>;
+
+// MIGRATIONS[n] upgrades a lockfile from schema version n + 1 to n + 2
+const MIGRATIONS: &[Migration] = &[
+    v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8,
+];
+
+pub fn run(lockfile: Value, from: u64) -> Result<Value> {
+    let Value::Object(mut lockfile) = lockfile else {
+        return Err(anyhow!("lockfile is not a JSON object"));
+    };
+
+    if from == 0 {
+        return Err(anyhow!("lockfile schema version 0 does not exist"));
+    }
+
+    for migration in MIGRATIONS.iter().skip(usize::try_from(from)? - 1) {
+        lockfile = migration(lockfile)?;
+    }
+
+    Ok(Value::Object(lockfile))
+}
+
+fn v1_to_v2(mut lockfile: Map<String, Value>) -> Result<Map<String, Value>> {
+    let plugins = lockfile
+        .get_mut("plugins")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| anyhow!("lockfile has no plugins"))?;
+
+    for plugin in plugins.iter_mut().filter_map(Value::as_object_mut) {
+        plugin.entry("channel").or_insert_with(|| "release".into());
+        plugin.entry("pinned").or_insert(Value::Bool(false));
+
+        let Some(dependencies) = plugin.get_mut("dependencies").and_then(Value::as_array_mut)
+        else {
+            continue;
+        };
+
+        // v1 never persisted whether a dependency was required, so assume it was
+        // rather than letting orphan removal delete something still in use
+        for dep in dependencies.iter_mut().filter_map(Value::as_object_mut) {
+            dep.entry("required").or_insert(Value::Bool(true));
+        }
+    }
+
+    lockfile.insert(String::from("schema_version"), Value::from(2));
+
+    Ok(lockfile)
+}
+
+fn v2_to_v3(mut lockfile: Map<String, Value>) -> Result<Map<String, Value>> {
+    let loader = lockfile
+        .get_mut("loader")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| anyhow!("lockfile has no loader"))?;
+
+    // The build is left unresolved, the next install will resolve and lock it
+    for key in ["url", "file", "checksum"] {
+        loader.entry(key).or_insert(Value::Null);
+    }
+
+    lockfile.insert(String::from("schema_version"), Value::from(3));
+
+    Ok(lockfile)
+}
+
+// Every migration shares the same signature, even those which can't fail, and spells out
+// the shape it writes so later changes to the types can't alter it
+#[allow(clippy::unnecessary_wraps)]
+fn v3_to_v4(mut lockfile: Map<String, Value>) -> Result<Map<String, Value>> {
+    lockfile
+        .entry("jvm")
+        .or_insert_with(|| json!({ "heap": "2G", "flags": "aikar" }));
+
+    lockfile.insert(String::from("schema_version"), Value::from(4));
+
+    Ok(lockfile)
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn v4_to_v5(mut lockfile: Map<String, Value>) -> Result<Map<String, Value>> {
+    lockfile.entry("scripts").or_insert(Value::Null);
+
+    lockfile.insert(String::from("schema_version"), Value::from(5));
+
+    Ok(lockfile)
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn v5_to_v6(mut lockfile: Map<String, Value>) -> Result<Map<String, Value>> {
+    lockfile
+        .entry("properties")
+        .or_insert_with(|| Value::Object(Map::new()));
+
+    lockfile.insert(String::from("schema_version"), Value::from(6));
+
+    Ok(lockfile)
+}
+
+// Earlier versions of mup signed eula.txt without asking, so nobody is recorded as having accepted
+#[allow(clippy::unnecessary_wraps)]
+fn v6_to_v7(mut lockfile: Map<String, Value>) -> Result<Map<String, Value>> {
+    lockfile.entry("eula").or_insert(Value::Null);
+
+    lockfile.insert(String::from("schema_version"), Value::from(7));
+
+    Ok(lockfile)
+}
+
+#[allow(clippy::unnecessary_wraps)]
+fn v7_to_v8(mut lockfile: Map<String, Value>) -> Result<Map<String, Value>> {
+    lockfile
+        .entry("players")
+        .or_insert_with(|| json!({ "whitelist": [], "ops": [], "banned": [] }));
+
+    lockfile.insert(String::from("schema_version"), Value::from(8));
+
+    Ok(lockfile)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{run, SCHEMA_VERSION};
+    use crate::server::lockfile::Lockfile;
+
+    fn v1() -> serde_json::Value {
+        json!({
+            "loader": {
+                "name": "paper",
+                "minecraft_version": "1.21.1",
+                "version": "latest"
+            },
+            "plugins": [{
+                "slug": "luckperms",
+                "id": "Vebnzrzj",
+                "version": "OrIs0S6b",
+                "dependencies": [{ "id": "vault" }],
+                "source": "modrinth#https://cdn.modrinth.com/LuckPerms-Bukkit-5.4.141.jar",
+                "checksum": null
+            }]
+        })
+    }
+
+    #[test]
+    fn migrates_v1_to_latest() {
+        let migrated = run(v1(), 1).unwrap();
+        assert_eq!(migrated["schema_version"], SCHEMA_VERSION);
+
+        let lf: Lockfile = serde_json::from_value(migrated).unwrap();
+
+        assert!(lf.plugins[0].dependencies[0].required);
+        assert!(!lf.plugins[0].pinned);
+        assert!(lf.loader.url.is_none());
+        assert_eq!(lf.jvm.heap, "2G");
+        assert!(lf.eula.is_none());
+        assert!(lf.players.whitelist.is_empty());
+    }
+
+    #[test]
+    fn keeps_existing_values() {
+        let mut lockfile = v1();
+        lockfile["plugins"][0]["dependencies"][0]["required"] = json!(false);
+        lockfile["jvm"] = json!({ "heap": "8G", "flags": "none" });
+
+        let lf: Lockfile = serde_json::from_value(run(lockfile, 1).unwrap()).unwrap();
+
+        assert!(!lf.plugins[0].dependencies[0].required);
+        assert_eq!(lf.jvm.heap, "8G");
+    }
+
+    #[test]
+    fn rejects_version_zero() {
+        assert!(run(v1(), 0).is_err());
+    }
+}
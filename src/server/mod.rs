@@ -1,11 +1,21 @@
 use anyhow::{anyhow, Result};
 use clap::Subcommand;
 
+mod clean;
+mod config;
 mod eula;
 pub mod lockfile;
+mod migrate;
+mod players;
+mod rcon;
+mod run;
+mod scripts;
+mod upgrade;
+mod verify;
 
 use lockfile::Lockfile;
 
+use crate::java;
 use crate::loader;
 use crate::plugin;
 
@@ -13,20 +23,136 @@ use crate::plugin;
 pub enum Server {
     /// Initialize a server in the current directory
     Init {
-        /// Minecraft version of the server
+        /// Minecraft version of the server.
+        /// Proxies take their own version instead, such as 3.3.0-SNAPSHOT for Velocity, or latest
         #[arg(short, long, required = true)]
         minecraft_version: String,
 
         /// Which loader to use
         #[arg(short, long, required = true)]
         loader: String,
+
+        /// Allow experimental loader builds
+        #[arg(short, long, action)]
+        experimental: bool,
+
+        /// Accept Minecraft's EULA without being asked
+        #[arg(long, action)]
+        accept_eula: bool,
     },
 
-    /// Sign the eula.txt
+    /// Accept Minecraft's EULA and sign eula.txt
     Sign,
 
     /// Install all mods from the current lockfile
-    Install,
+    Install {
+        /// Accept Minecraft's EULA without being asked
+        #[arg(long, action)]
+        accept_eula: bool,
+    },
+
+    /// Check installed jarfiles against the lockfile
+    Verify {
+        /// Redownload missing or modified jarfiles
+        #[arg(long, action)]
+        fix: bool,
+    },
+
+    /// Remove jarfiles which are not tracked by the lockfile
+    Clean {
+        /// Only print what would be removed
+        #[arg(long, action)]
+        dry_run: bool,
+
+        /// Never remove jarfiles whose name matches this glob
+        #[arg(short, long, value_name = "glob")]
+        keep: Vec<String>,
+
+        /// Move jarfiles into .mup-quarantine instead of deleting them
+        #[arg(short, long, action)]
+        quarantine: bool,
+
+        /// Also remove loader jarfiles superseded by the current build
+        #[arg(short, long, action)]
+        loaders: bool,
+    },
+
+    /// Move the server to another Minecraft version or loader, along with its plugins
+    Upgrade {
+        /// Minecraft version to move to.
+        /// Proxies take their own version instead, such as 3.3.0-SNAPSHOT for Velocity, or latest
+        #[arg(short, long, required_unless_present = "loader")]
+        minecraft_version: Option<String>,
+
+        /// Loader to switch to
+        #[arg(short, long, value_parser = loader::parse)]
+        loader: Option<String>,
+
+        /// Drop plugins which have no compatible version instead of aborting
+        #[arg(short, long, action)]
+        force: bool,
+    },
+
+    /// Launch the installed server
+    Run {
+        /// Heap size to run with from now on, such as 4G
+        #[arg(long, value_parser = lockfile::parse_heap)]
+        heap: Option<String>,
+
+        /// JVM flags to run with from now on
+        #[arg(long, value_enum)]
+        flags: Option<lockfile::Flags>,
+
+        /// Extra arguments passed on to the server
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+
+    /// Read and change server.properties
+    #[command(subcommand)]
+    Config(config::Config),
+
+    /// Manage who may join the server
+    #[command(subcommand)]
+    Whitelist(players::Players),
+
+    /// Manage who may run operator commands
+    #[command(subcommand)]
+    Ops(players::Players),
+
+    /// Manage who is banned from the server
+    #[command(subcommand)]
+    Ban(players::Players),
+
+    /// Run a command on the running server over RCON
+    Rcon {
+        /// The command to run, without a leading slash
+        #[arg(required = true, trailing_var_arg = true)]
+        command: Vec<String>,
+
+        /// Host the server is running on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+
+    /// Run commands on the running server over RCON, read from stdin
+    Console {
+        /// Host the server is running on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+
+    /// Generate start.sh and a systemd unit, kept up to date with the lockfile
+    Scripts {
+        /// Where the server lives on the machine running the systemd unit,
+        /// defaults to the current directory
+        #[arg(short, long)]
+        directory: Option<String>,
+
+        /// Also generate a Dockerfile
+        #[arg(long, action)]
+        docker: bool,
+    },
 }
 
 pub fn action(server: &Server) -> Result<()> {
@@ -34,13 +160,40 @@ pub fn action(server: &Server) -> Result<()> {
         Server::Init {
             minecraft_version,
             loader,
-        } => init(minecraft_version, loader),
-        Server::Sign => eula::sign(),
-        Server::Install => install(),
+            experimental,
+            accept_eula,
+        } => init(minecraft_version, loader, *experimental, *accept_eula),
+        Server::Sign => eula::accept(),
+        Server::Install { accept_eula } => install(*accept_eula),
+        Server::Verify { fix } => verify::verify(*fix),
+        Server::Clean {
+            dry_run,
+            keep,
+            quarantine,
+            loaders,
+        } => clean::clean(*dry_run, keep, *quarantine, *loaders),
+        Server::Upgrade {
+            minecraft_version,
+            loader,
+            force,
+        } => upgrade::upgrade(minecraft_version.as_deref(), loader.as_deref(), *force),
+        Server::Run { heap, flags, args } => run::run(heap.as_deref(), *flags, args),
+        Server::Config(c) => config::action(c),
+        Server::Whitelist(p) => players::action(players::Kind::Whitelist, p),
+        Server::Ops(p) => players::action(players::Kind::Ops, p),
+        Server::Ban(p) => players::action(players::Kind::Banned, p),
+        Server::Rcon { command, host } => rcon::rcon(host, command),
+        Server::Console { host } => rcon::console(host),
+        Server::Scripts { directory, docker } => scripts::scripts(directory.as_deref(), *docker),
     }
 }
 
-fn init(minecraft_version: &str, loader: &str) -> Result<()> {
+fn init(
+    minecraft_version: &str,
+    loader: &str,
+    experimental: bool,
+    accept_eula: bool,
+) -> Result<()> {
     let mut lf = Lockfile::with_params(minecraft_version, loader)?;
 
     if !lf.is_initialized() {
@@ -49,34 +202,50 @@ fn init(minecraft_version: &str, loader: &str) -> Result<()> {
         ));
     }
 
-    loader::fetch(
-        &lf.loader.name,
+    let build = lf.loader.kind()?.fetch(
         &lf.loader.minecraft_version,
         &lf.loader.version,
+        experimental,
     )?;
 
-    eula::sign()?;
+    lf.loader.lock(build);
+    lf.save()?;
+
+    java::check(lf.loader.required_java());
+
+    eula::ensure(&mut lf, accept_eula)?;
 
     Ok(())
 }
 
-fn install() -> Result<()> {
+fn install(accept_eula: bool) -> Result<()> {
     let mut lf = Lockfile::init()?;
     if !lf.is_initialized() {
         return Err(anyhow!("failed to read lockfile"));
     }
 
-    loader::fetch(
-        &lf.loader.name,
-        &lf.loader.minecraft_version,
-        &lf.loader.version,
-    )?;
+    let kind = lf.loader.kind()?;
+
+    java::check(lf.loader.required_java());
+
+    let build = if let Some(mut build) = lf.loader.build() {
+        kind.download(&mut build)?;
+        build
+    } else {
+        kind.fetch(&lf.loader.minecraft_version, &lf.loader.version, false)?
+    };
+
+    lf.loader.lock(build);
+    lf.save()?;
 
     for entry in &lf.plugins {
         plugin::download(&entry.source, &lf.loader.name, entry.checksum.as_ref())?;
     }
 
-    eula::sign()?;
+    config::apply(&lf.properties)?;
+    players::apply(&lf.players)?;
+
+    eula::ensure(&mut lf, accept_eula)?;
 
     Ok(())
 }
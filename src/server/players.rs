@@ -0,0 +1,364 @@
+use std::{
+    env, fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use log::info;
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{config::Properties, lockfile::Lockfile};
+
+const MOJANG_API_URL: &str = "https://api.mojang.com";
+
+#[derive(Debug, Subcommand)]
+pub enum Players {
+    /// Add a player to the list
+    #[command(
+        after_help = "Online-mode servers look the player up with Mojang's API, set MUP_MOJANG_API_URL to use another"
+    )]
+    Add {
+        /// Name of the player
+        player: String,
+
+        /// Also record the player in the lockfile, so install applies it
+        #[arg(short, long, action)]
+        lock: bool,
+    },
+
+    /// Remove a player from the list, and from the lockfile
+    Remove {
+        /// Name or UUID of the player
+        player: String,
+    },
+
+    /// Print every player on the list, marking those recorded in the lockfile
+    List,
+}
+
+/// The player lists a server keeps
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Whitelist,
+    Ops,
+    Banned,
+}
+
+impl Kind {
+    const fn path(self) -> &'static str {
+        match self {
+            Self::Whitelist => "whitelist.json",
+            Self::Ops => "ops.json",
+            Self::Banned => "banned-players.json",
+        }
+    }
+
+    // Fields the server expects besides the UUID and name
+    fn defaults(self) -> Result<Map<String, Value>> {
+        let fields: Vec<(&str, Value)> = match self {
+            Self::Whitelist => vec![],
+            Self::Ops => vec![
+                ("level", Value::from(4)),
+                ("bypassesPlayerLimit", Value::from(false)),
+            ],
+            Self::Banned => vec![
+                (
+                    "created",
+                    Value::from(timestamp(
+                        SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+                    )),
+                ),
+                ("source", Value::from("mup")),
+                ("expires", Value::from("forever")),
+                ("reason", Value::from("Banned by an operator.")),
+            ],
+        };
+
+        Ok(fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect())
+    }
+}
+
+/// A player as recorded in the lockfile, resolved once so installs don't depend on Mojang's API
+#[derive(Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Player {
+    pub name: String,
+    pub uuid: String,
+}
+
+/// Players declared in the lockfile
+#[derive(Default, Deserialize, Serialize)]
+pub struct Declared {
+    pub whitelist: Vec<Player>,
+    pub ops: Vec<Player>,
+    pub banned: Vec<Player>,
+}
+
+impl Declared {
+    const fn get_mut(&mut self, kind: Kind) -> &mut Vec<Player> {
+        match kind {
+            Kind::Whitelist => &mut self.whitelist,
+            Kind::Ops => &mut self.ops,
+            Kind::Banned => &mut self.banned,
+        }
+    }
+
+    const fn get(&self, kind: Kind) -> &Vec<Player> {
+        match kind {
+            Kind::Whitelist => &self.whitelist,
+            Kind::Ops => &self.ops,
+            Kind::Banned => &self.banned,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Profile {
+    id: String,
+    name: String,
+}
+
+pub fn action(kind: Kind, players: &Players) -> Result<()> {
+    let mut lf = Lockfile::init()?;
+    if !lf.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    match players {
+        Players::Add { player, lock } => {
+            let player = resolve(player)?;
+
+            add(kind, &player)?;
+
+            if *lock {
+                let declared = lf.players.get_mut(kind);
+                if !declared.contains(&player) {
+                    declared.push(player);
+                }
+
+                lf.save()?;
+            }
+        }
+        Players::Remove { player } => {
+            let mut entries = load(kind)?;
+            let before = entries.len();
+
+            entries.retain(|e| !matches(e, player));
+
+            let declared = lf.players.get_mut(kind);
+            let locked = declared.len();
+
+            declared.retain(|p| !p.name.eq_ignore_ascii_case(player) && p.uuid != *player);
+
+            if entries.len() == before && declared.len() == locked {
+                return Err(anyhow!("{player} is not in {}", kind.path()));
+            }
+
+            save(kind, &entries)?;
+            lf.save()?;
+        }
+        Players::List => {
+            for entry in load(kind)? {
+                let name = entry
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+
+                if lf
+                    .players
+                    .get(kind)
+                    .iter()
+                    .any(|p| p.name.eq_ignore_ascii_case(name))
+                {
+                    println!("{name} (locked)");
+                } else {
+                    println!("{name}");
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Add every player declared in the lockfile to the server's lists
+pub fn apply(declared: &Declared) -> Result<()> {
+    for kind in [Kind::Whitelist, Kind::Ops, Kind::Banned] {
+        for player in declared.get(kind) {
+            add(kind, player)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn add(kind: Kind, player: &Player) -> Result<()> {
+    let mut entries = load(kind)?;
+
+    if entries.iter().any(|e| matches(e, &player.uuid)) {
+        info!("{} is already in {}", player.name, kind.path());
+        return Ok(());
+    }
+
+    let mut entry = Map::new();
+    entry.insert(String::from("uuid"), Value::from(player.uuid.clone()));
+    entry.insert(String::from("name"), Value::from(player.name.clone()));
+    entry.extend(kind.defaults()?);
+
+    entries.push(entry);
+
+    save(kind, &entries)
+}
+
+fn matches(entry: &Map<String, Value>, player: &str) -> bool {
+    let field = |key| entry.get(key).and_then(Value::as_str).unwrap_or_default();
+
+    field("name").eq_ignore_ascii_case(player) || field("uuid").eq_ignore_ascii_case(player)
+}
+
+fn load(kind: Kind) -> Result<Vec<Map<String, Value>>> {
+    if !Path::new(kind.path()).exists() {
+        return Ok(vec![]);
+    }
+
+    Ok(serde_json::from_str(&fs::read_to_string(kind.path())?)?)
+}
+
+fn save(kind: Kind, entries: &[Map<String, Value>]) -> Result<()> {
+    info!("writing {}", kind.path());
+
+    let mut contents = serde_json::to_string_pretty(entries)?;
+    contents.push('\n');
+
+    fs::write(kind.path(), contents)?;
+
+    Ok(())
+}
+
+// Offline servers derive UUIDs from player names instead of asking Mojang
+fn resolve(name: &str) -> Result<Player> {
+    let online = Properties::load()?.get("online-mode") != Some("false");
+
+    if !online {
+        return Ok(Player {
+            name: name.to_string(),
+            uuid: offline_uuid(name),
+        });
+    }
+
+    // Overridable so the API can be stood in for
+    let base_url = env::var("MUP_MOJANG_API_URL").unwrap_or_else(|_| MOJANG_API_URL.to_string());
+
+    info!("looking up {name}");
+
+    let response = ureq::get(&format!("{base_url}/users/profiles/minecraft/{name}"))
+        .set("User-Agent", mup::FAKE_USER_AGENT)
+        .call()
+        .map_err(|e| match e {
+            ureq::Error::Status(404, _) => anyhow!("player {name} does not exist"),
+            e => e.into(),
+        })?;
+
+    let profile: Profile = response.into_json()?;
+
+    Ok(Player {
+        name: profile.name,
+        uuid: hyphenate(&profile.id),
+    })
+}
+
+// The same version 3 UUID the server generates, from an MD5 hash of "OfflinePlayer:<name>"
+fn offline_uuid(name: &str) -> String {
+    let mut hash: [u8; 16] = Md5::digest(format!("OfflinePlayer:{name}")).into();
+
+    hash[6] = (hash[6] & 0x0f) | 0x30;
+    hash[8] = (hash[8] & 0x3f) | 0x80;
+
+    hyphenate(&mup::to_hex(&hash))
+}
+
+// Formatted the way the server writes ban dates, always in UTC
+fn timestamp(secs: u64) -> String {
+    let days = secs / 86400;
+    let time = secs % 86400;
+
+    // Convert days since the epoch to a civil date, counting in 400 year eras from 0000-03-01
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} +0000",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+fn hyphenate(id: &str) -> String {
+    if id.len() != 32 {
+        return id.to_string();
+    }
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &id[..8],
+        &id[8..12],
+        &id[12..16],
+        &id[16..20],
+        &id[20..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hyphenate, offline_uuid, timestamp};
+
+    #[test]
+    fn offline_uuid_matches_server() {
+        assert_eq!(
+            offline_uuid("Notch"),
+            "b50ad385-829d-3141-a216-7e7d7539ba7f"
+        );
+    }
+
+    #[test]
+    fn hyphenates_mojang_ids() {
+        assert_eq!(
+            hyphenate("069a79f444e94726a5befca90e38aaf5"),
+            "069a79f4-44e9-4726-a5be-fca90e38aaf5"
+        );
+
+        // Anything that isn't an undashed UUID is left alone
+        assert_eq!(
+            hyphenate("069a79f4-44e9-4726-a5be-fca90e38aaf5"),
+            "069a79f4-44e9-4726-a5be-fca90e38aaf5"
+        );
+        assert_eq!(hyphenate("Notch"), "Notch");
+    }
+
+    #[test]
+    fn formats_ban_timestamps() {
+        assert_eq!(timestamp(0), "1970-01-01 00:00:00 +0000");
+        assert_eq!(timestamp(951_825_600), "2000-02-29 12:00:00 +0000");
+        assert_eq!(timestamp(1_700_000_000), "2023-11-14 22:13:20 +0000");
+    }
+}
@@ -0,0 +1,302 @@
+use std::{
+    io::{self, BufRead, IsTerminal, Read, Write},
+    net::TcpStream,
+};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use super::config::Properties;
+
+const DEFAULT_PORT: &str = "25575";
+
+// Servers split responses every 4096 UTF-16 characters before encoding them as UTF-8, so a
+// body can take up to three bytes per character, plus the ID, type and terminators
+const MAX_PACKET_LENGTH: usize = 3 * 4096 + 10;
+
+const TYPE_RESPONSE: i32 = 0;
+const TYPE_COMMAND: i32 = 2;
+const TYPE_LOGIN: i32 = 3;
+
+/// A client for the Source RCON protocol, as implemented by Minecraft servers
+struct Client {
+    stream: TcpStream,
+    next_id: i32,
+}
+
+impl Client {
+    /// Connect using the port and password from server.properties
+    fn open(host: &str) -> Result<Self> {
+        let properties = Properties::load()?;
+
+        let port = properties.get("rcon.port").unwrap_or(DEFAULT_PORT);
+        let password = properties
+            .get("rcon.password")
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| {
+                anyhow!(
+                    "rcon.password is not set, run mup server config set rcon.password <password>"
+                )
+            })?;
+
+        if properties.get("enable-rcon") != Some("true") {
+            info!("enable-rcon is not true in server.properties, connecting anyway");
+        }
+
+        Self::connect(&format!("{host}:{port}"), password)
+    }
+
+    fn connect(addr: &str, password: &str) -> Result<Self> {
+        info!("connecting to {addr}");
+
+        let mut client = Self {
+            stream: TcpStream::connect(addr)?,
+            next_id: 0,
+        };
+
+        let id = client.send(TYPE_LOGIN, password)?;
+
+        // The server answers a failed login with a request ID of -1
+        let (response_id, _, _) = client.receive()?;
+        if response_id != id {
+            return Err(anyhow!("rcon login failed, check rcon.password"));
+        }
+
+        Ok(client)
+    }
+
+    fn command(&mut self, command: &str) -> Result<String> {
+        let id = self.send(TYPE_COMMAND, command)?;
+
+        // Long responses are split over several packets, and nothing marks the last one,
+        // so follow up with a packet the server can only answer after it has finished
+        let sentinel = self.send(TYPE_RESPONSE, "")?;
+
+        let mut response = String::new();
+
+        loop {
+            let (response_id, _, body) = self.receive()?;
+
+            if response_id == sentinel {
+                return Ok(response);
+            }
+
+            if response_id == id {
+                response.push_str(&body);
+            }
+        }
+    }
+
+    fn send(&mut self, kind: i32, body: &str) -> Result<i32> {
+        self.next_id += 1;
+        let id = self.next_id;
+
+        // ID, type, body and two null terminators
+        let length = i32::try_from(4 + 4 + body.len() + 2)?;
+
+        let mut packet = Vec::with_capacity(4 + body.len() + 10);
+        packet.extend(length.to_le_bytes());
+        packet.extend(id.to_le_bytes());
+        packet.extend(kind.to_le_bytes());
+        packet.extend(body.as_bytes());
+        packet.extend([0, 0]);
+
+        self.stream.write_all(&packet)?;
+
+        Ok(id)
+    }
+
+    fn receive(&mut self) -> Result<(i32, i32, String)> {
+        let mut length = [0; 4];
+        self.stream.read_exact(&mut length)?;
+
+        let length = usize::try_from(i32::from_le_bytes(length))?;
+        if !(10..=MAX_PACKET_LENGTH).contains(&length) {
+            return Err(anyhow!("received a malformed rcon packet"));
+        }
+
+        let mut packet = vec![0; length];
+        self.stream.read_exact(&mut packet)?;
+
+        let id = i32::from_le_bytes(packet[0..4].try_into()?);
+        let kind = i32::from_le_bytes(packet[4..8].try_into()?);
+        let body = String::from_utf8_lossy(&packet[8..length - 2]).to_string();
+
+        Ok((id, kind, body))
+    }
+}
+
+/// Run a single command on the server, printing its response
+pub fn rcon(host: &str, command: &[String]) -> Result<()> {
+    let mut client = Client::open(host)?;
+
+    let response = client.command(&command.join(" "))?;
+    if !response.is_empty() {
+        println!("{response}");
+    }
+
+    Ok(())
+}
+
+/// Run commands read from stdin until it is closed
+pub fn console(host: &str) -> Result<()> {
+    let mut client = Client::open(host)?;
+
+    let interactive = io::stdin().is_terminal();
+
+    loop {
+        if interactive {
+            print!("> ");
+            io::stdout().flush()?;
+        }
+
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+
+        let command = line.trim();
+        if command.is_empty() {
+            continue;
+        }
+
+        let response = client.command(command)?;
+        if !response.is_empty() {
+            println!("{response}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread::{self, JoinHandle},
+    };
+
+    use super::{Client, TYPE_COMMAND, TYPE_LOGIN, TYPE_RESPONSE};
+
+    const TYPE_AUTH_RESPONSE: i32 = 2;
+
+    // Run a stand-in server for a single connection
+    fn serve(handler: impl FnOnce(TcpStream) + Send + 'static) -> (String, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        let handle = thread::spawn(move || handler(listener.accept().unwrap().0));
+
+        (addr, handle)
+    }
+
+    fn read_packet(stream: &mut TcpStream) -> (i32, i32, String) {
+        let mut length = [0; 4];
+        stream.read_exact(&mut length).unwrap();
+
+        let mut packet = vec![0; usize::try_from(i32::from_le_bytes(length)).unwrap()];
+        stream.read_exact(&mut packet).unwrap();
+
+        let id = i32::from_le_bytes(packet[0..4].try_into().unwrap());
+        let kind = i32::from_le_bytes(packet[4..8].try_into().unwrap());
+        let body = String::from_utf8(packet[8..packet.len() - 2].to_vec()).unwrap();
+
+        (id, kind, body)
+    }
+
+    fn write_packet(stream: &mut TcpStream, id: i32, kind: i32, body: &str) {
+        let length = i32::try_from(body.len() + 10).unwrap();
+
+        stream.write_all(&length.to_le_bytes()).unwrap();
+        stream.write_all(&id.to_le_bytes()).unwrap();
+        stream.write_all(&kind.to_le_bytes()).unwrap();
+        stream.write_all(body.as_bytes()).unwrap();
+        stream.write_all(&[0, 0]).unwrap();
+    }
+
+    fn accept_login(stream: &mut TcpStream) {
+        let (id, kind, body) = read_packet(stream);
+        assert_eq!(kind, TYPE_LOGIN);
+        assert_eq!(body, "hunter2");
+
+        write_packet(stream, id, TYPE_AUTH_RESPONSE, "");
+    }
+
+    #[test]
+    fn login_succeeds() {
+        let (addr, handle) = serve(|mut stream| accept_login(&mut stream));
+
+        assert!(Client::connect(&addr, "hunter2").is_ok());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn login_fails() {
+        let (addr, handle) = serve(|mut stream| {
+            read_packet(&mut stream);
+            write_packet(&mut stream, -1, TYPE_AUTH_RESPONSE, "");
+        });
+
+        let error = Client::connect(&addr, "wrong").err().unwrap();
+        assert!(error.to_string().contains("login failed"));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn joins_responses_until_sentinel() {
+        let (addr, handle) = serve(|mut stream| {
+            accept_login(&mut stream);
+
+            let (id, kind, body) = read_packet(&mut stream);
+            assert_eq!(kind, TYPE_COMMAND);
+            assert_eq!(body, "list");
+
+            let (sentinel, kind, _) = read_packet(&mut stream);
+            assert_eq!(kind, TYPE_RESPONSE);
+
+            write_packet(&mut stream, id, TYPE_RESPONSE, &"a".repeat(4096));
+            write_packet(&mut stream, id, TYPE_RESPONSE, "b");
+            write_packet(&mut stream, sentinel, TYPE_RESPONSE, "Unknown request 0");
+        });
+
+        let mut client = Client::connect(&addr, "hunter2").unwrap();
+        let response = client.command("list").unwrap();
+
+        assert_eq!(response, format!("{}b", "a".repeat(4096)));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn accepts_multi_byte_responses() {
+        // 4096 characters, but 8192 bytes once encoded
+        let body = "§".repeat(4096);
+        let expected = body.clone();
+
+        let (addr, handle) = serve(move |mut stream| {
+            accept_login(&mut stream);
+
+            let (id, _, _) = read_packet(&mut stream);
+            let (sentinel, _, _) = read_packet(&mut stream);
+
+            write_packet(&mut stream, id, TYPE_RESPONSE, &body);
+            write_packet(&mut stream, sentinel, TYPE_RESPONSE, "");
+        });
+
+        let mut client = Client::connect(&addr, "hunter2").unwrap();
+
+        assert_eq!(client.command("list").unwrap(), expected);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_length() {
+        for length in [4, 3 * 4096 + 11, -1] {
+            let (addr, handle) = serve(move |mut stream| {
+                read_packet(&mut stream);
+                stream.write_all(&i32::to_le_bytes(length)).unwrap();
+            });
+
+            assert!(Client::connect(&addr, "hunter2").is_err());
+            handle.join().unwrap();
+        }
+    }
+}
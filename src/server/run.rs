@@ -0,0 +1,80 @@
+use std::{path::Path, process::Command};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use super::lockfile::{Flags, Lockfile};
+use crate::java;
+
+pub fn run(heap: Option<&str>, flags: Option<Flags>, args: &[String]) -> Result<()> {
+    let mut lf = Lockfile::init()?;
+    if !lf.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    if heap.is_some() || flags.is_some() {
+        if let Some(heap) = heap {
+            lf.jvm.heap = heap.to_string();
+        }
+
+        if let Some(flags) = flags {
+            lf.jvm.flags = flags;
+        }
+
+        lf.save()?;
+    }
+
+    let mut command = launch_args(&lf)?;
+    command.extend(args.iter().cloned());
+
+    if let Some(entrypoint) = &lf.loader.entrypoint {
+        let path = entrypoint.trim_start_matches('@');
+
+        if !Path::new(path).exists() {
+            return Err(anyhow!(
+                "{path} is missing, run mup server verify --fix to restore it"
+            ));
+        }
+    }
+
+    let java = java::find_for(lf.loader.required_java())?;
+
+    // Release the lockfile so mup can still be used while the server is running
+    drop(lf);
+
+    info!("launching {} {}", java.display(), command.join(" "));
+
+    let status = Command::new(java).args(command).status()?;
+
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+
+    Ok(())
+}
+
+/// Everything passed to java to launch the locked loader
+pub fn launch_args(lf: &Lockfile) -> Result<Vec<String>> {
+    let entrypoint = lf
+        .loader
+        .entrypoint
+        .as_ref()
+        .ok_or_else(|| anyhow!("the loader is not installed, run mup server install first"))?;
+
+    // Installed Forge-style servers launch from an argument file rather than a jarfile
+    let launch = if entrypoint.starts_with('@') {
+        vec![entrypoint.clone()]
+    } else {
+        vec![String::from("-jar"), entrypoint.clone()]
+    };
+
+    let mut args = lf.jvm.args();
+    args.extend(launch);
+
+    // Proxies have no GUI to disable, and may refuse unknown arguments
+    if !lf.loader.kind()?.is_proxy() {
+        args.push(String::from("nogui"));
+    }
+
+    Ok(args)
+}
@@ -0,0 +1,144 @@
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::{env, fmt::Write, fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use super::{
+    lockfile::{Jvm, Lockfile, Scripts},
+    run,
+};
+
+const START_SCRIPT_PATH: &str = "start.sh";
+const UNIT_PATH: &str = "mup.service";
+const DOCKERFILE_PATH: &str = "Dockerfile";
+
+pub fn scripts(directory: Option<&str>, docker: bool) -> Result<()> {
+    let mut lf = Lockfile::init()?;
+    if !lf.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    if lf.loader.entrypoint.is_none() {
+        return Err(anyhow!(
+            "the loader is not installed, run mup server install first"
+        ));
+    }
+
+    let directory = match directory {
+        Some(directory) => directory.to_string(),
+        None => env::current_dir()?.to_string_lossy().to_string(),
+    };
+
+    // Saving writes the scripts, and rewrites them whenever the lockfile changes from now on
+    lf.scripts = Some(Scripts { directory, docker });
+    lf.save()
+}
+
+/// Write the launch scripts the lockfile asks for, if any
+pub fn write(lf: &Lockfile) -> Result<()> {
+    let Some(scripts) = &lf.scripts else {
+        return Ok(());
+    };
+
+    // Nothing can be launched until the loader has been installed
+    let Ok(args) = run::launch_args(lf) else {
+        return Ok(());
+    };
+
+    let start = format!(
+        "#!/bin/sh\n\
+         # Generated by mup from mup.lock, do not edit\n\
+         cd \"$(dirname \"$0\")\" || exit 1\n\
+         exec java {} \"$@\"\n",
+        args.iter().map(|a| quote(a)).collect::<Vec<_>>().join(" ")
+    );
+
+    write_if_changed(START_SCRIPT_PATH, &start)?;
+
+    #[cfg(unix)]
+    fs::set_permissions(START_SCRIPT_PATH, fs::Permissions::from_mode(0o755))?;
+
+    let mut unit = format!(
+        "# Generated by mup from mup.lock, do not edit\n\
+         [Unit]\n\
+         Description=Minecraft server ({} {})\n\
+         Wants=network-online.target\n\
+         After=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         WorkingDirectory={directory}\n\
+         ExecStart={directory}/{START_SCRIPT_PATH}\n\
+         Restart=on-failure\n\
+         RestartSec=10\n\
+         # The JVM exits with 143 when stopped with SIGTERM\n\
+         SuccessExitStatus=0 143\n",
+        lf.loader.name,
+        lf.loader.minecraft_version,
+        directory = scripts.directory,
+    );
+
+    if let Some(limit) = memory_limit(&lf.jvm.heap) {
+        writeln!(unit, "MemoryMax={limit}M")?;
+    }
+
+    unit.push_str("\n[Install]\nWantedBy=multi-user.target\n");
+
+    write_if_changed(UNIT_PATH, &unit)?;
+
+    if scripts.docker {
+        let port = if lf.loader.kind()?.is_proxy() {
+            25577
+        } else {
+            25565
+        };
+
+        let java = lf.loader.required_java().unwrap_or(21);
+
+        let dockerfile = format!(
+            "# Generated by mup from mup.lock, do not edit\n\
+             FROM eclipse-temurin:{java}-jre\n\
+             WORKDIR /server\n\
+             COPY . .\n\
+             EXPOSE {port}\n\
+             CMD [\"./{START_SCRIPT_PATH}\"]\n"
+        );
+
+        write_if_changed(DOCKERFILE_PATH, &dockerfile)?;
+    }
+
+    Ok(())
+}
+
+fn write_if_changed(path: &str, contents: &str) -> Result<()> {
+    if fs::read_to_string(path).is_ok_and(|c| c == contents) {
+        return Ok(());
+    }
+
+    info!("writing {path}");
+
+    fs::write(Path::new(path), contents)?;
+
+    Ok(())
+}
+
+// The heap plus a quarter on top for the JVM itself, in megabytes
+fn memory_limit(heap: &str) -> Option<u64> {
+    let megabytes = Jvm::heap_megabytes(heap)?;
+
+    Some(megabytes + megabytes / 4)
+}
+
+// Arguments are single quoted unless they are obviously safe for the shell
+fn quote(arg: &str) -> String {
+    if arg
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_=.:/@+,%".contains(c))
+    {
+        return arg.to_string();
+    }
+
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
@@ -0,0 +1,183 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use super::lockfile::{self, Lockfile};
+use crate::{loader, plugin};
+
+// What happens to a locked plugin when the server moves
+enum Outcome {
+    Upgrade(plugin::Info),
+    Stay,
+    Drop(anyhow::Error),
+}
+
+pub fn upgrade(minecraft_version: Option<&str>, loader: Option<&str>, force: bool) -> Result<()> {
+    let mut lf = Lockfile::init()?;
+    if !lf.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    let name = loader.unwrap_or(&lf.loader.name).to_string();
+    let kind = loader::get(&name)?;
+
+    // Switching loaders without naming a version keeps the current one
+    let mut build = kind.resolve(
+        minecraft_version.unwrap_or(&lf.loader.minecraft_version),
+        "latest",
+        false,
+    )?;
+
+    // Plugins are checked against what the lockfile will look like afterwards
+    let mut target = Lockfile::default();
+    target.loader = lockfile::Loader {
+        name: name.clone(),
+        minecraft_version: build.minecraft_version.clone(),
+        ..lockfile::Loader::default()
+    };
+
+    println!(
+        "loader: {} {} -> {name} {} ({})",
+        lf.loader.name, lf.loader.minecraft_version, build.minecraft_version, build.version
+    );
+
+    let mut outcomes = vec![];
+
+    for entry in &lf.plugins {
+        // Held plugins may only stay at their locked version
+        let version = if entry.pinned {
+            &entry.version
+        } else {
+            "latest"
+        };
+
+        let fetched = entry.provider().and_then(|provider| {
+            plugin::fetch(&target, provider, &entry.id, version, entry.channel)
+        });
+
+        let outcome = match fetched {
+            Ok(info) if info.version == entry.version => Outcome::Stay,
+            Ok(info) => Outcome::Upgrade(info),
+            Err(e) if e.is::<plugin::Incompatible>() => Outcome::Drop(e),
+            Err(e) => return Err(e.context(format!("failed to check {}", entry.slug))),
+        };
+
+        match &outcome {
+            Outcome::Upgrade(info) => {
+                println!(
+                    "upgrade: {} {} -> {}",
+                    entry.slug, entry.version, info.version
+                );
+            }
+            Outcome::Stay => println!("stay: {} {}", entry.slug, entry.version),
+            Outcome::Drop(e) => println!("drop: {} ({e})", entry.slug),
+        }
+
+        outcomes.push((entry.slug.clone(), outcome));
+    }
+
+    let blockers = outcomes
+        .iter()
+        .filter(|(_, o)| matches!(o, Outcome::Drop(_)))
+        .count();
+
+    if blockers > 0 && !force {
+        return Err(anyhow!(
+            "{blockers} plugins have no compatible version, run with --force to drop them"
+        ));
+    }
+
+    let missing = missing_dependencies(&lf, &outcomes);
+
+    for (slug, dep) in &missing {
+        println!("missing: {slug} requires {dep}, which is not locked");
+    }
+
+    if !missing.is_empty() && !force {
+        return Err(anyhow!(
+            "{} required dependencies would not be locked, add them with mup plugin add or run with --force to upgrade anyway",
+            missing.len()
+        ));
+    }
+
+    // Everything is downloaded before anything is removed, so a failed
+    // download leaves the old server intact
+    kind.download(&mut build)?;
+
+    download(&lf, &outcomes, &name)?;
+
+    for (slug, outcome) in outcomes {
+        let idx = lf.plugins.iter().position(|p| p.slug == slug).unwrap();
+        let old_path = lf.plugins[idx].get_file_path(&lf.loader.name)?;
+
+        let new_path = match outcome {
+            Outcome::Stay => continue,
+            Outcome::Upgrade(info) => {
+                let new_path = info.get_file_path(&name)?;
+                lf.plugins[idx] = info;
+                Some(new_path)
+            }
+            Outcome::Drop(_) => {
+                lf.plugins.remove(idx);
+                None
+            }
+        };
+
+        if new_path.as_ref() != Some(&old_path) && Path::new(&old_path).exists() {
+            info!("removing {old_path}");
+            fs::remove_file(old_path)?;
+        }
+    }
+
+    lf.loader.name = name;
+    lf.loader.lock(build);
+    lf.save()
+}
+
+// Required dependencies of the plugins that would be locked after the upgrade, which are
+// not among them, as pairs of the dependent plugin and the dependency
+fn missing_dependencies(lf: &Lockfile, outcomes: &[(String, Outcome)]) -> Vec<(String, String)> {
+    let after: Vec<&plugin::Info> = outcomes
+        .iter()
+        .filter_map(|(slug, outcome)| match outcome {
+            Outcome::Upgrade(info) => Some(info),
+            Outcome::Stay => lf.get(slug).ok(),
+            Outcome::Drop(_) => None,
+        })
+        .collect();
+
+    let mut missing = vec![];
+
+    for entry in &after {
+        for dep in entry.dependencies.iter().filter(|d| d.required) {
+            if !after.iter().any(|p| [&p.slug, &p.id].contains(&&dep.id)) {
+                missing.push((entry.slug.clone(), dep.id.clone()));
+            }
+        }
+    }
+
+    missing
+}
+
+// Fetch every plugin that ends up in a new file, before any old ones are removed
+fn download(lf: &Lockfile, outcomes: &[(String, Outcome)], name: &str) -> Result<()> {
+    for (slug, outcome) in outcomes {
+        let entry = lf.get(slug)?;
+
+        match outcome {
+            // Moving between loaders can move the plugin directory too
+            Outcome::Stay
+                if entry.get_file_path(name)? != entry.get_file_path(&lf.loader.name)? =>
+            {
+                plugin::download(&entry.source, name, entry.checksum.as_ref())?;
+            }
+            Outcome::Upgrade(info) => {
+                plugin::download(&info.source, name, info.checksum.as_ref())?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
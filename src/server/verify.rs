@@ -0,0 +1,115 @@
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Result};
+use log::info;
+
+use super::{clean, lockfile::Lockfile};
+use crate::plugin;
+
+pub fn verify(fix: bool) -> Result<()> {
+    let mut lf = Lockfile::init()?;
+    if !lf.is_initialized() {
+        return Err(anyhow!("failed to read lockfile"));
+    }
+
+    let kind = lf.loader.kind()?;
+    let mut broken = 0;
+
+    if let Some(mut build) = lf.loader.build() {
+        // Installers are removed once they have run, so check what they installed instead
+        let (path, checksum) = match &build.entrypoint {
+            Some(entrypoint) if kind.is_installer() => {
+                (entrypoint.trim_start_matches('@').to_string(), None)
+            }
+            _ => (build.file.clone(), build.checksum.as_deref()),
+        };
+
+        if let Some(status) = status(Path::new(&path), checksum)? {
+            println!("{status}: {path}");
+
+            if fix {
+                kind.download(&mut build)?;
+
+                lf.loader.lock(build);
+                lf.save()?;
+            } else {
+                broken += 1;
+            }
+        }
+    } else if !has_unlocked_loader(&lf)? {
+        println!("missing: {} loader jarfile", lf.loader.name);
+
+        if fix {
+            let build = kind.fetch(&lf.loader.minecraft_version, &lf.loader.version, false)?;
+
+            lf.loader.lock(build);
+            lf.save()?;
+        } else {
+            broken += 1;
+        }
+    }
+
+    for entry in &lf.plugins {
+        let path = entry.get_file_path(&lf.loader.name)?;
+
+        let Some(status) = status(Path::new(&path), entry.checksum.as_deref())? else {
+            continue;
+        };
+
+        println!("{status}: {path}");
+
+        if fix {
+            plugin::download(&entry.source, &lf.loader.name, entry.checksum.as_ref())?;
+        } else {
+            broken += 1;
+        }
+    }
+
+    for path in clean::untracked(&lf)? {
+        println!("untracked: {}", path.display());
+    }
+
+    if broken > 0 {
+        return Err(anyhow!(
+            "{broken} jarfiles are missing or modified, run with --fix to redownload them"
+        ));
+    }
+
+    Ok(())
+}
+
+// Returns what is wrong with a jarfile, if anything
+fn status(path: &Path, checksum: Option<&str>) -> Result<Option<&'static str>> {
+    if !path.exists() {
+        return Ok(Some("missing"));
+    }
+
+    let Some(checksum) = checksum else {
+        info!("{} has no checksum, skipping", path.display());
+        return Ok(None);
+    };
+
+    if mup::matches_checksum(path, checksum)? {
+        info!("{} is intact", path.display());
+        return Ok(None);
+    }
+
+    Ok(Some("modified"))
+}
+
+// Lockfiles which predate build locking only know the loader name and Minecraft version
+fn has_unlocked_loader(lf: &Lockfile) -> Result<bool> {
+    for entry in fs::read_dir(".")? {
+        let filename = entry?.file_name();
+
+        if lf
+            .loader
+            .kind()?
+            .is_loader_jar(&lf.loader.minecraft_version, &filename.to_string_lossy())
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}